@@ -12,10 +12,21 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use askme::{get_yn_from_input, prelude::*, wait_for};
+use askme::commands::{self, Command};
+use askme::exam::{Exam, ExamItem, DEFAULT_PASS_MARK};
+use askme::generate::true_false_statement;
+use askme::mastery::run_until_mastered;
+use askme::scoring::{Scoring, Summary};
+use askme::selector::AdaptiveSelector;
+use askme::timing::Clock;
+use askme::ui::{self, Span, Tone};
+use askme::{
+    get_optional_input, get_select_input, get_yn_until, prelude::*, print_response_times,
+    print_warning, wait_for,
+};
 use colored::Colorize;
 use figlet_rs::FIGfont;
-use rand::{seq::SliceRandom, thread_rng, Rng};
+use std::time::Instant;
 
 const CORRECT_FEEDBACK_STR: &str = "✔️ That's correct!";
 const INCORRECT_FEEDBACK_STR: &str = "❌ Not quite correct..";
@@ -25,59 +36,62 @@ pub struct Settings {
     pub wait_duration: f64,
     pub loop_questions: bool,
     pub show_correct: bool,
+    pub until_mastered: Option<u32>,
+    pub adaptive: bool,
+    pub count: Option<usize>,
+    pub question_time: Option<f64>,
+    pub time_limit: Option<f64>,
+    pub exam: bool,
+    pub pass_mark: Option<f64>,
+    pub scoring: Scoring,
 }
 
 pub struct App {
     set: AskmeSet,
     settings: Settings,
-    correct_count: i32,
+    asked_count: usize,
+    clock: Clock,
+    results: Vec<QuestionResult>,
 }
 
-impl App {
-    fn get_random_answer_from_set(&self, exclude_question_ans: Option<&Question>) -> String {
-        // If exclude_question_ans is Some, return the answer with random index to that question
-        if let Some(question) = exclude_question_ans {
-            if let Some(ans) = &question.answers.choose(&mut thread_rng()) {
-                ans.to_string()
-            } else {
-                panic!("Failed to get random answer from set!");
-            }
-        } else {
-            // If exclude_question_ans is None, return a random answer from the set
-            let rand_answer = &self
-                .set
-                .questions
-                .iter()
-                .flat_map(|qn| &qn.answers)
-                .collect::<Vec<&String>>();
-            if let Some(ans) = rand_answer.choose(&mut thread_rng()) {
-                ans.to_string()
-            } else {
-                panic!("Failed to get random answer from set!");
-            }
-        }
-    }
+/// Show the statement "`title` is `answer`" to be judged.
+fn show_statement(title: &str, answer: &str) {
+    ui::show_question("Is this correct? (y/n)");
+    ui::print_spans(&[
+        Span::plain(" \""),
+        Span::new(Tone::Emphasis, format!("{} is {}", title, answer)),
+        Span::plain("\""),
+    ]);
+    ui::print(Tone::Plain, "");
+}
 
+impl App {
     fn provide_qn_feedback(&self, question: &Question, is_correct: bool) {
         match is_correct {
             true => {
-                println!("{}\n", CORRECT_FEEDBACK_STR.green().bold());
+                ui::print_spaced(Tone::Correct, CORRECT_FEEDBACK_STR);
             }
             false => {
-                println!("{}\n", INCORRECT_FEEDBACK_STR.red().bold());
+                ui::print_spaced(Tone::Incorrect, INCORRECT_FEEDBACK_STR);
 
                 if self.settings.show_correct {
                     if question.answers.len() == 1 {
-                        println!(
-                            "The answer to \"{}\" is \"{}\"!\n",
-                            question.title,
-                            question.answers.first().unwrap()
+                        ui::print_spaced(
+                            Tone::Plain,
+                            &format!(
+                                "The answer to \"{}\" is \"{}\"!",
+                                question.title,
+                                question.answers.first().unwrap()
+                            ),
                         );
                     } else {
                         let all_correct_answers_string = question.answers.join(", ");
-                        println!(
-                            "The answers to \"{}\" are \"{}\"!\n",
-                            question.title, all_correct_answers_string
+                        ui::print_spaced(
+                            Tone::Plain,
+                            &format!(
+                                "The answers to \"{}\" are \"{}\"!",
+                                question.title, all_correct_answers_string
+                            ),
                         );
                     }
                 }
@@ -85,37 +99,166 @@ impl App {
         }
     }
 
-    fn ask_question(&mut self, question: &Question) {
-        let rand_ans = match thread_rng().gen_bool(0.5) {
-            true => self.get_random_answer_from_set(Some(question)),
-            false => self.get_random_answer_from_set(None),
+    /// Read a yes/no answer that can be skipped, which returns `None`.
+    fn get_exam_yn(&self, deadline: Option<Instant>) -> Option<bool> {
+        let deadline = match deadline {
+            Some(deadline) => deadline,
+            None => {
+                let options = ["Yes", "No", "(skip)"].map(String::from).to_vec();
+                return match get_select_input("Answer:", options)? {
+                    0 => Some(true),
+                    1 => Some(false),
+                    _ => None,
+                };
+            }
         };
 
-        println!(
-            "{} (y/n)\n \"{} is {}\"\n",
-            "Is this correct?".bold(),
-            question.title.italic().bold(),
-            rand_ans.italic().bold()
+        loop {
+            match get_optional_input(Some(deadline))?.to_lowercase().as_str() {
+                "y" | "yes" => break Some(true),
+                "n" | "no" => break Some(false),
+                _ => print_warning("answer with y or n"),
+            }
+        }
+    }
+
+    /// Ask every question without feedback, then review the submitted answers.
+    fn run_exam(&mut self, questions: Vec<Question>) {
+        // The statements stay the same when a question is revisited
+        let (statements, truths): (Vec<String>, Vec<bool>) = questions
+            .iter()
+            .map(|qn| true_false_statement(qn, &self.set.all_questions))
+            .unzip();
+
+        let items = questions
+            .iter()
+            .zip(&statements)
+            .zip(&truths)
+            .map(|((qn, statement), &truth)| ExamItem {
+                title: format!("{} is {}", qn.title, statement),
+                correct: if truth { "yes" } else { "no" }.to_string(),
+                points: qn.points,
+            })
+            .collect();
+        let mut exam = Exam::new(items);
+
+        while let Some(idx) = exam.next_question() {
+            if self.clock.is_over() {
+                break;
+            }
+
+            show_statement(&questions[idx].title, &statements[idx]);
+            let answer = self
+                .get_exam_yn(self.clock.question_deadline())
+                .map(|yes| if yes { "yes" } else { "no" }.to_string());
+
+            exam.answer(idx, answer);
+            ui::print(Tone::Plain, "");
+        }
+
+        let scores = exam
+            .answers()
+            .iter()
+            .zip(&truths)
+            .map(|(answer, &truth)| match answer.as_deref() {
+                Some("yes") if truth => 1.0,
+                Some("no") if !truth => 1.0,
+                _ => 0.0,
+            })
+            .collect::<Vec<f64>>();
+
+        let points = questions
+            .iter()
+            .zip(&scores)
+            .map(|(question, &score)| self.settings.scoring.points(question, score, false))
+            .collect::<Vec<f64>>();
+        let results = exam.results(&scores, &points);
+
+        exam.print_review(
+            &results,
+            self.settings.pass_mark.unwrap_or(DEFAULT_PASS_MARK),
         );
 
-        let user_answer = get_yn_from_input();
-        let is_rand_ans_correct = question.answers.contains(&rand_ans) == user_answer;
+        self.asked_count += questions.len();
+        for result in results {
+            ui::show_result(&result);
+            self.results.push(result);
+        }
+    }
+
+    /// Forget the last answer, as if its question hadn't been asked.
+    fn undo(&mut self) {
+        if commands::undo_last(&mut self.results).is_some() {
+            self.asked_count -= 1;
+        }
+    }
 
-        if is_rand_ans_correct {
-            self.correct_count += 1;
+    /// Ask a question, returning whether it was answered correctly, or `None`
+    /// if the session time ran out (or the user quit) before it could be asked.
+    fn ask_question(&mut self, question: &Question) -> Option<bool> {
+        if self.clock.is_over() || commands::has_quit() {
+            return None;
         }
 
-        self.provide_qn_feedback(question, is_rand_ans_correct);
+        commands::begin_question(std::slice::from_ref(question), &self.results);
+
+        let (rand_ans, is_true) = true_false_statement(question, &self.set.all_questions);
+
+        show_statement(&question.title, &rand_ans);
+
+        let started = Instant::now();
+        let user_answer = get_yn_until(self.clock.question_deadline());
+
+        // Without an answer, either the time ran out or a command ended the prompt
+        let interrupt = commands::interrupt_of(&user_answer);
+        match interrupt {
+            Some(Command::Quit) => return None,
+            Some(Command::Undo) => {
+                self.undo();
+                return self.ask_question(question);
+            }
+            _ => (), // Skipped and revealed questions count as wrong
+        }
+
+        // Timed out questions count as wrong
+        let is_rand_ans_correct = user_answer == Some(is_true);
+        let score = match is_rand_ans_correct {
+            true => self
+                .settings
+                .scoring
+                .after_hints(1.0, commands::hints_taken()),
+            false => 0.0,
+        };
+
+        self.asked_count += 1;
+        let result = QuestionResult {
+            title: question.title.clone(),
+            answer: user_answer.map(|yes| if yes { "yes" } else { "no" }.to_string()),
+            score,
+            points: self.settings.scoring.points(question, score, false),
+            max_points: question.points,
+            response_time: started.elapsed(),
+            timed_out: user_answer.is_none() && interrupt.is_none(),
+        };
+        ui::show_result(&result);
+        self.results.push(result);
+
+        if interrupt.is_none() {
+            self.provide_qn_feedback(question, is_rand_ans_correct);
+        }
         wait_for(self.settings.wait_duration);
+        Some(is_rand_ans_correct)
     }
 }
 
-impl AskmeMode<Settings, i32> for App {
+impl AskmeMode<Settings, Summary> for App {
     fn new(set: AskmeSet, settings: Settings) -> Self {
         App {
+            asked_count: 0,
+            clock: Clock::new(settings.question_time, settings.time_limit),
+            results: Vec::new(),
             set,
             settings,
-            correct_count: 0,
         }
     }
 
@@ -132,30 +275,71 @@ impl AskmeMode<Settings, i32> for App {
         format!(" {}\n", self.set.subtitle.blue())
     }
 
+    fn get_asked_count(&self) -> usize {
+        self.asked_count
+    }
+
+    fn get_set(&self) -> &AskmeSet {
+        &self.set
+    }
+
+    fn get_results(&self) -> &[QuestionResult] {
+        &self.results
+    }
+
     fn run_set(&mut self) {
         let qns = match self.settings.shuffle {
             false => self.set.questions.clone(),
             true => shuffle_arr(&self.set.questions.clone()),
         };
 
-        qns.iter().for_each(|question| self.ask_question(question));
+        if self.settings.exam {
+            return self.run_exam(qns);
+        }
+
+        if self.settings.adaptive {
+            let count = self.settings.count.unwrap_or(qns.len());
+            let mut selector = AdaptiveSelector::from_history(qns, &self.results);
+            return selector.run(count, |question| {
+                self.ask_question(question)
+                    .map(|correct| if correct { 1.0 } else { 0.0 })
+            });
+        }
+
+        match self.settings.until_mastered {
+            Some(streak) => run_until_mastered(qns, streak, |question| self.ask_question(question)),
+            None => {
+                for (asked, question) in qns.iter().enumerate() {
+                    ui::show_progress(asked, qns.len());
+                    if self.ask_question(question).is_none() {
+                        break;
+                    }
+                }
+            }
+        }
     }
 
-    fn run(&mut self) -> Result<i32, &str> {
+    fn run(&mut self) -> Result<Summary, AskmeError> {
         if self.set.questions.is_empty() {
-            return Err("no questions provided!");
+            return Err(AskmeError::new("no questions provided!", None));
         };
 
-        println!("{}", self.get_title());
-        println!("{}", self.get_subtitle());
+        ui::show_title(&self.set.title, &self.set.subtitle);
 
+        self.clock.start();
         match self.settings.loop_questions {
-            true => loop {
-                self.run_set()
-            },
+            true => {
+                while !self.clock.is_over() && !commands::has_quit() {
+                    self.run_set()
+                }
+            }
             false => self.run_set(),
         }
 
-        Ok(self.correct_count)
+        if self.clock.is_limited() {
+            print_response_times(&self.results);
+        }
+
+        Ok(Summary::from_results(&self.results))
     }
 }
@@ -12,75 +12,156 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use askme::prelude::*;
-use askme::print_correct_answers;
+use askme::cli::{self, CommonArgs, Mode};
+use askme::config::Options;
+use askme::scoring::Scoring;
 
 mod app;
 
+const MODE: Mode = Mode {
+    name: "correctme",
+    single_only: true,
+    adaptive: true,
+};
+
 use app::App;
 use clap::Parser;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    filename: String,
+    #[command(flatten)]
+    common: CommonArgs,
 
     #[arg(
-        default_value_t = 1.0,
-        long = "duration",
-        short = 'd',
-        help = "Choose how long the delay is between new questions (seconds, can be decimal)"
+        long,
+        value_name = "STREAK",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "1",
+        conflicts_with_all = ["loop_questions", "exam"],
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Re-ask missed questions until each is answered correctly STREAK times in a row"
     )]
-    wait_duration: f64,
+    until_mastered: Option<u32>,
 
     #[arg(
         long,
-        short = 'L',
-        help = "Choose if you would like to be quizzed in a loop"
+        short = 'A',
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        conflicts_with_all = ["until_mastered", "exam"],
+        help = "Pick questions by their weight and how often they were missed"
     )]
-    loop_questions: bool,
+    adaptive: Option<bool>,
 
-    #[arg(long, short = 's', help = "Enable shuffling")]
-    shuffle: bool,
+    #[arg(
+        long,
+        short = 'c',
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Only ask N questions, drawn at random from the set (with --adaptive: per pass)"
+    )]
+    count: Option<u64>,
+
+    #[arg(
+        long,
+        short = 's',
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        help = "Enable shuffling"
+    )]
+    shuffle: Option<bool>,
 
     #[arg(
         long,
         short = 'S',
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
         help = "show the correct answer(s) if an answer was wrong"
     )]
-    show_correct: bool,
+    show_correct: Option<bool>,
+
+    #[arg(
+        long,
+        short = 't',
+        value_name = "SECONDS",
+        help = "Time limit for answering each question"
+    )]
+    question_time: Option<f64>,
+
+    #[arg(
+        long,
+        short = 'T',
+        value_name = "SECONDS",
+        help = "Time limit for the whole session"
+    )]
+    time_limit: Option<f64>,
+
+    #[arg(
+        long,
+        short = 'e',
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        conflicts_with = "loop_questions",
+        help = "Exam mode: no feedback until all answers are submitted"
+    )]
+    exam: Option<bool>,
+
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        help = "Percentage needed to pass the exam [default: 50]"
+    )]
+    pass_mark: Option<f64>,
 }
 
-impl From<Args> for app::Settings {
-    fn from(val: Args) -> Self {
+impl Args {
+    /// The settings given on the command line.
+    fn options(&self) -> Options {
+        Options {
+            shuffle: self.shuffle,
+            show_correct: self.show_correct,
+            until_mastered: self.until_mastered,
+            adaptive: self.adaptive,
+            count: self.count.map(|count| count as usize),
+            question_time: self.question_time,
+            time_limit: self.time_limit,
+            exam: self.exam,
+            pass_mark: self.pass_mark,
+            ..self.common.options()
+        }
+    }
+}
+
+impl From<Options> for app::Settings {
+    fn from(val: Options) -> Self {
         app::Settings {
-            loop_questions: val.loop_questions,
-            show_correct: val.show_correct,
-            wait_duration: val.wait_duration,
-            shuffle: val.shuffle,
+            shuffle: val.shuffle.unwrap_or_default(),
+            wait_duration: val.wait_duration.unwrap_or(1.0),
+            loop_questions: val.loop_questions.unwrap_or_default(),
+            show_correct: val.show_correct.unwrap_or_default(),
+            until_mastered: val.until_mastered,
+            adaptive: val.adaptive.unwrap_or_default(),
+            count: val.count,
+            question_time: val.question_time,
+            time_limit: val.time_limit,
+            exam: val.exam.unwrap_or_default(),
+            pass_mark: val.pass_mark,
+            scoring: Scoring::from(&val),
         }
     }
 }
 
 fn main() {
     let args = Args::parse();
-
-    let set = match AskmeSet::from_file(&args.filename) {
-        Ok(s) => s,
-        Err(e) => {
-            askme::print_err(&format!("{}", e));
-            std::process::exit(1)
-        }
-    };
-
-    let set_questions = set.questions.len();
-
-    let mut app = App::new(set, app::Settings::from(args));
-
-    let correct_count = match app.run() {
-        Ok(c) => c,
-        Err(e) => panic!("error: {}", e),
-    };
-
-    print_correct_answers(correct_count, set_questions);
+    cli::run::<App, app::Settings>(&MODE, &args.common, &args.options());
 }
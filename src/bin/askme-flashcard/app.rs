@@ -0,0 +1,280 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::time::Instant;
+
+use askme::commands::{self, Command};
+use askme::mastery::run_until_mastered;
+use askme::prelude::*;
+use askme::scoring::{Scoring, Summary};
+use askme::selector::AdaptiveSelector;
+use askme::ui::{self, Span, Tone};
+use askme::{get_select_input, shuffle_arr, wait_for, wait_for_enter};
+use colored::Colorize;
+use figlet_rs::FIGfont;
+
+static GRADES: [Grade; 4] = [Grade::Again, Grade::Hard, Grade::Good, Grade::Easy];
+
+/// How well the user remembered a card.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Grade {
+    Again,
+    Hard,
+    Good,
+    Easy,
+}
+
+impl Grade {
+    pub fn score(&self) -> f64 {
+        match self {
+            Grade::Again => 0.0,
+            Grade::Hard => 0.5,
+            Grade::Good | Grade::Easy => 1.0,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Grade::Again => "again",
+            Grade::Hard => "hard",
+            Grade::Good => "good",
+            Grade::Easy => "easy",
+        }
+    }
+
+    pub fn tone(&self) -> Tone {
+        match self {
+            Grade::Again => Tone::Incorrect,
+            Grade::Hard => Tone::Partial,
+            Grade::Good | Grade::Easy => Tone::Correct,
+        }
+    }
+}
+
+impl fmt::Display for Grade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+pub struct Settings {
+    pub shuffle: bool,
+    pub loop_questions: bool,
+    pub wait_duration: f64,
+    pub until_mastered: Option<u32>,
+    pub adaptive: bool,
+    pub count: Option<usize>,
+    pub scoring: Scoring,
+}
+
+pub struct App {
+    set: AskmeSet,
+    settings: Settings,
+    asked_count: usize,
+    grades: Vec<(String, Grade)>, // Grades given to each card, in order
+    results: Vec<QuestionResult>,
+}
+
+impl App {
+    /// Forget the last grade, as if its card hadn't been shown.
+    fn undo(&mut self) {
+        if commands::undo_last(&mut self.results).is_some() {
+            self.asked_count -= 1;
+            self.grades.pop();
+        }
+    }
+
+    /// Show a card and let the user grade it, returning the grade, or `None`
+    /// if the user quit.
+    fn ask_question(&mut self, question: &Question) -> Option<Grade> {
+        if commands::has_quit() {
+            return None;
+        }
+
+        commands::begin_question(std::slice::from_ref(question), &self.results);
+        ui::show_question(&question.title);
+        let started = Instant::now();
+        wait_for_enter("Press enter to reveal the answer");
+
+        let grade = match commands::take_interrupt() {
+            Some(Command::Quit) => return None,
+            Some(Command::Undo) => {
+                self.undo();
+                return self.ask_question(question);
+            }
+            // Skipped cards count as forgotten
+            Some(Command::Skip) => None,
+            // The answer was already revealed
+            Some(_) => Some(self.grade_card()?),
+            None => {
+                // Ordering questions are remembered as the items in order
+                let answer = match question.kind {
+                    QuestionKind::Order => question.answers.join(" → "),
+                    QuestionKind::Single | QuestionKind::List => question.answers.join(", "),
+                };
+                ui::print_spans(&[Span::plain(" "), Span::new(Tone::Emphasis, answer)]);
+                ui::print(Tone::Plain, "");
+                Some(self.grade_card()?)
+            }
+        };
+
+        let answer = grade.map(|grade| grade.name().to_string());
+        let grade = grade.unwrap_or(Grade::Again);
+
+        let score = self
+            .settings
+            .scoring
+            .after_hints(grade.score(), commands::hints_taken());
+
+        self.asked_count += 1;
+        self.grades.push((question.title.clone(), grade));
+        let result = QuestionResult {
+            title: question.title.clone(),
+            answer,
+            score,
+            points: self.settings.scoring.points(question, score, false),
+            max_points: question.points,
+            response_time: started.elapsed(),
+            timed_out: false,
+        };
+        ui::show_result(&result);
+        self.results.push(result);
+
+        ui::print(Tone::Plain, "");
+        wait_for(self.settings.wait_duration);
+        Some(grade)
+    }
+
+    /// Let the user grade the card, or `None` if they quit.
+    fn grade_card(&self) -> Option<Grade> {
+        let options = GRADES.iter().map(|grade| grade.to_string()).collect();
+        get_select_input("How well did you remember it?", options).map(|idx| GRADES[idx])
+    }
+
+    pub fn print_grades(&self) {
+        ui::print(Tone::Bold, "Card grades:");
+
+        for question in &self.set.questions {
+            let grades = self
+                .grades
+                .iter()
+                .filter(|(title, _)| *title == question.title)
+                .map(|(_, grade)| grade)
+                .collect::<Vec<&Grade>>();
+
+            if grades.is_empty() {
+                continue;
+            }
+
+            let mut spans = vec![Span::plain(format!(" {}: ", question.title))];
+            for (i, grade) in grades.iter().enumerate() {
+                if i > 0 {
+                    spans.push(Span::plain(", "));
+                }
+                spans.push(Span::new(grade.tone(), grade.name()));
+            }
+            ui::print_spans(&spans);
+        }
+        ui::print(Tone::Plain, "");
+    }
+}
+
+impl AskmeMode<Settings, Summary> for App {
+    fn new(set: AskmeSet, settings: Settings) -> Self {
+        App {
+            asked_count: 0,
+            grades: Vec::new(),
+            results: Vec::new(),
+            set,
+            settings,
+        }
+    }
+
+    fn get_title(&self) -> String {
+        let font = FIGfont::standard().unwrap();
+        font.convert(&self.set.title)
+            .unwrap()
+            .to_string()
+            .cyan()
+            .to_string()
+    }
+
+    fn get_subtitle(&self) -> String {
+        format!(" {}\n", self.set.subtitle.blue())
+    }
+
+    fn get_asked_count(&self) -> usize {
+        self.asked_count
+    }
+
+    fn get_set(&self) -> &AskmeSet {
+        &self.set
+    }
+
+    fn get_results(&self) -> &[QuestionResult] {
+        &self.results
+    }
+
+    fn run_set(&mut self) {
+        let qns = match self.settings.shuffle {
+            false => self.set.questions.clone(),
+            true => shuffle_arr(&self.set.questions.clone()),
+        };
+
+        if self.settings.adaptive {
+            let count = self.settings.count.unwrap_or(qns.len());
+            let mut selector = AdaptiveSelector::from_history(qns, &self.results);
+            return selector.run(count, |question| {
+                self.ask_question(question).map(|grade| grade.score())
+            });
+        }
+
+        match self.settings.until_mastered {
+            Some(streak) => run_until_mastered(qns, streak, |question| {
+                self.ask_question(question)
+                    .map(|grade| grade.score() >= 1.0)
+            }),
+            None => {
+                for (asked, question) in qns.iter().enumerate() {
+                    ui::show_progress(asked, qns.len());
+                    if self.ask_question(question).is_none() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    fn run(&mut self) -> Result<Summary, AskmeError> {
+        if self.set.questions.is_empty() {
+            return Err(AskmeError::new("no questions provided!", None));
+        };
+
+        ui::show_title(&self.set.title, &self.set.subtitle);
+
+        match self.settings.loop_questions {
+            true => {
+                while !commands::has_quit() {
+                    self.run_set()
+                }
+            }
+            false => self.run_set(),
+        }
+
+        self.print_grades();
+
+        Ok(Summary::from_results(&self.results))
+    }
+}
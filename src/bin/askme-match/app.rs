@@ -0,0 +1,295 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use askme::commands::{self, Command};
+use askme::grading::{check_answer, option_labels, parse_order};
+use askme::mastery::run_rounds_until_mastered;
+use askme::prelude::*;
+use askme::random::rng;
+use askme::scoring::{Scoring, Summary};
+use askme::timing::Clock;
+use askme::ui::{self, Span, Tone};
+use askme::{get_input_until, print_info, print_response_times, print_warning, wait_for};
+use colored::Colorize;
+use figlet_rs::FIGfont;
+use rand::seq::SliceRandom;
+use std::time::Instant;
+
+const CORRECT_FEEDBACK_STR: &str = "✔️";
+const INCORRECT_FEEDBACK_STR: &str = "❌";
+
+pub struct Settings {
+    pub shuffle: bool,
+    pub loop_questions: bool,
+    pub show_correct: bool,
+    pub wait_duration: f64,
+    pub pairs: usize,
+    pub until_mastered: Option<u32>,
+    pub question_time: Option<f64>,
+    pub time_limit: Option<f64>,
+    pub scoring: Scoring,
+}
+
+pub struct App {
+    set: AskmeSet,
+    settings: Settings,
+    asked_count: usize,
+    clock: Clock,
+    results: Vec<QuestionResult>,
+    rounds: Vec<usize>, // Number of results each round added, in order
+}
+
+impl App {
+    fn print_round(&self, questions: &[Question], answers: &[String]) {
+        let title_width = questions
+            .iter()
+            .map(|qn| qn.title.chars().count())
+            .max()
+            .unwrap_or(0);
+        let labels = option_labels(answers.len());
+
+        for (i, (question, answer)) in questions.iter().zip(answers).enumerate() {
+            ui::print_spans(&[
+                Span::plain(format!(" {:>2}. ", i + 1)),
+                Span::new(
+                    Tone::Bold,
+                    format!("{:<width$}", question.title, width = title_width),
+                ),
+                Span::plain(format!("    {}) {}", labels[i], answer)),
+            ]);
+        }
+        ui::print(Tone::Plain, "");
+    }
+
+    fn provide_round_feedback(&self, questions: &[Question], answers: &[String], picks: &[usize]) {
+        for (i, (question, &pick)) in questions.iter().zip(picks).enumerate() {
+            match check_answer(question, &answers[pick], true) {
+                true => ui::print_spans(&[
+                    Span::new(Tone::Correct, CORRECT_FEEDBACK_STR),
+                    Span::plain(format!(
+                        " {}. {} → {}",
+                        i + 1,
+                        question.title,
+                        answers[pick]
+                    )),
+                ]),
+                false => {
+                    ui::print_spans(&[
+                        Span::new(Tone::Incorrect, INCORRECT_FEEDBACK_STR),
+                        Span::plain(format!(" {}. {} → ", i + 1, question.title)),
+                        Span::new(Tone::Incorrect, &answers[pick]),
+                    ]);
+
+                    if self.settings.show_correct {
+                        ui::print_spans(&[
+                            Span::plain("     The answer is: "),
+                            Span::new(Tone::Bold, question.answers.join(", ")),
+                        ]);
+                    }
+                }
+            }
+        }
+        ui::print(Tone::Plain, "");
+    }
+
+    /// Forget the answers of the last round, as if it hadn't been asked.
+    fn undo(&mut self) {
+        let len = self.rounds.pop().unwrap_or_default();
+        commands::undo(&mut self.results, len);
+        self.asked_count -= len;
+        print_info("the answers of the last round no longer count");
+    }
+
+    /// Ask a round of questions, returning whether each was matched right, or
+    /// `None` if the session time ran out (or the user quit) before it could
+    /// be asked.
+    fn ask_round(&mut self, questions: &[Question]) -> Option<Vec<bool>> {
+        if self.clock.is_over() || commands::has_quit() {
+            return None;
+        }
+
+        commands::begin_question(questions, &self.results);
+
+        let mut rng = rng();
+
+        // Questions with several answers get one of them picked per round
+        let mut answers = questions
+            .iter()
+            .map(|qn| qn.answers.choose(&mut rng).cloned().unwrap_or_default())
+            .collect::<Vec<String>>();
+        answers.shuffle(&mut rng);
+
+        ui::show_question("Match each item with its answer:");
+        self.print_round(questions, &answers);
+
+        // The time limit applies to the whole round
+        let deadline = self.clock.question_deadline();
+        let started = Instant::now();
+
+        let picks = loop {
+            let input = match get_input_until(deadline) {
+                Some(input) => input,
+                None => break None,
+            };
+            if let Some(picks) = parse_order(&input, questions.len()) {
+                break Some(picks);
+            }
+            print_warning("type the letter matching each item in order, e.g. \"bca\"");
+        };
+        let response_time = started.elapsed();
+
+        // Without an answer, either the time ran out or a command ended the prompt
+        let interrupt = commands::interrupt_of(&picks);
+        match interrupt {
+            Some(Command::Quit) => return None,
+            Some(Command::Undo) => {
+                self.undo();
+                return self.ask_round(questions);
+            }
+            _ => (), // Skipped and revealed rounds count as wrong
+        }
+
+        let mut round_correct = Vec::new();
+        for (i, question) in questions.iter().enumerate() {
+            let answer = picks.as_ref().map(|picks| answers[picks[i]].clone());
+            let correct = match &answer {
+                Some(answer) => check_answer(question, answer, true),
+                None => false,
+            };
+            round_correct.push(correct);
+
+            let score = match correct {
+                true => self
+                    .settings
+                    .scoring
+                    .after_hints(1.0, commands::hints_taken()),
+                false => 0.0,
+            };
+            let result = QuestionResult {
+                title: question.title.clone(),
+                timed_out: answer.is_none() && interrupt.is_none(),
+                answer,
+                score,
+                points: self.settings.scoring.points(question, score, false),
+                max_points: question.points,
+                response_time,
+            };
+            ui::show_result(&result);
+            self.results.push(result);
+        }
+        self.asked_count += questions.len();
+        self.rounds.push(questions.len());
+
+        match picks {
+            Some(picks) => self.provide_round_feedback(questions, &answers, &picks),
+            None => {
+                if self.settings.show_correct && interrupt.is_none() {
+                    for question in questions {
+                        ui::print_spans(&[
+                            Span::plain(format!(" {} → ", question.title)),
+                            Span::new(Tone::Bold, question.answers.join(", ")),
+                        ]);
+                    }
+                    ui::print(Tone::Plain, "");
+                }
+            }
+        }
+
+        wait_for(self.settings.wait_duration);
+        Some(round_correct)
+    }
+}
+
+impl AskmeMode<Settings, Summary> for App {
+    fn new(set: AskmeSet, settings: Settings) -> Self {
+        App {
+            asked_count: 0,
+            clock: Clock::new(settings.question_time, settings.time_limit),
+            results: Vec::new(),
+            rounds: Vec::new(),
+            set,
+            settings,
+        }
+    }
+
+    fn get_title(&self) -> String {
+        let font = FIGfont::standard().unwrap();
+        font.convert(&self.set.title)
+            .unwrap()
+            .to_string()
+            .cyan()
+            .to_string()
+    }
+
+    fn get_subtitle(&self) -> String {
+        format!(" {}\n", self.set.subtitle.blue())
+    }
+
+    fn get_asked_count(&self) -> usize {
+        self.asked_count
+    }
+
+    fn get_set(&self) -> &AskmeSet {
+        &self.set
+    }
+
+    fn get_results(&self) -> &[QuestionResult] {
+        &self.results
+    }
+
+    fn run_set(&mut self) {
+        let qns = match self.settings.shuffle {
+            false => self.set.questions.clone(),
+            true => shuffle_arr(&self.set.questions.clone()),
+        };
+
+        if let Some(streak) = self.settings.until_mastered {
+            let pairs = self.settings.pairs;
+            return run_rounds_until_mastered(qns, streak, pairs, |questions| {
+                self.ask_round(questions)
+            });
+        }
+
+        for (round, questions) in qns.chunks(self.settings.pairs).enumerate() {
+            ui::show_progress(round * self.settings.pairs, qns.len());
+            if self.ask_round(questions).is_none() {
+                break;
+            }
+        }
+    }
+
+    fn run(&mut self) -> Result<Summary, AskmeError> {
+        if self.set.questions.is_empty() {
+            return Err(AskmeError::new("no questions provided!", None));
+        };
+
+        ui::show_title(&self.set.title, &self.set.subtitle);
+
+        self.clock.start();
+        match self.settings.loop_questions {
+            true => {
+                while !self.clock.is_over() && !commands::has_quit() {
+                    self.run_set()
+                }
+            }
+            false => self.run_set(),
+        }
+
+        if self.clock.is_limited() {
+            print_response_times(&self.results);
+        }
+
+        Ok(Summary::from_results(&self.results))
+    }
+}
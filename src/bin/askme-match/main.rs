@@ -0,0 +1,139 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use askme::cli::{self, CommonArgs, Mode};
+use askme::config::Options;
+use askme::scoring::Scoring;
+
+mod app;
+
+const MODE: Mode = Mode {
+    name: "match",
+    single_only: true,
+    adaptive: false,
+};
+
+use app::App;
+use clap::Parser;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[command(flatten)]
+    common: CommonArgs,
+
+    #[arg(
+        long,
+        value_name = "STREAK",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "1",
+        conflicts_with = "loop_questions",
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Re-ask missed questions until each is matched correctly STREAK times in a row"
+    )]
+    until_mastered: Option<u32>,
+
+    #[arg(
+        long,
+        short = 's',
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        help = "Enable shuffling"
+    )]
+    shuffle: Option<bool>,
+
+    #[arg(
+        long,
+        short = 'S',
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        help = "show the correct answer(s) if an answer was wrong"
+    )]
+    show_correct: Option<bool>,
+
+    #[arg(
+        long,
+        short = 't',
+        value_name = "SECONDS",
+        help = "Time limit for answering each question"
+    )]
+    question_time: Option<f64>,
+
+    #[arg(
+        long,
+        short = 'T',
+        value_name = "SECONDS",
+        help = "Time limit for the whole session"
+    )]
+    time_limit: Option<f64>,
+
+    #[arg(
+        long,
+        short = 'n',
+        help = "Number of items to match in each round [default: 5]",
+        value_parser = clap::value_parser!(u8).range(2..=26),
+    )]
+    pairs: Option<u8>,
+
+    #[arg(
+        long,
+        short = 'c',
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Only ask N questions, drawn at random from the set"
+    )]
+    count: Option<u64>,
+}
+
+impl Args {
+    /// The settings given on the command line.
+    fn options(&self) -> Options {
+        Options {
+            shuffle: self.shuffle,
+            show_correct: self.show_correct,
+            pairs: self.pairs.map(usize::from),
+            until_mastered: self.until_mastered,
+            count: self.count.map(|count| count as usize),
+            question_time: self.question_time,
+            time_limit: self.time_limit,
+            ..self.common.options()
+        }
+    }
+}
+
+impl From<Options> for app::Settings {
+    fn from(val: Options) -> Self {
+        app::Settings {
+            shuffle: val.shuffle.unwrap_or_default(),
+            loop_questions: val.loop_questions.unwrap_or_default(),
+            show_correct: val.show_correct.unwrap_or_default(),
+            wait_duration: val.wait_duration.unwrap_or(1.0),
+            pairs: val.pairs.unwrap_or(5),
+            until_mastered: val.until_mastered,
+            question_time: val.question_time,
+            time_limit: val.time_limit,
+            scoring: Scoring::from(&val),
+        }
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+    cli::run::<App, app::Settings>(&MODE, &args.common, &args.options());
+}
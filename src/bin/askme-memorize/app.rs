@@ -12,13 +12,29 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use askme::commands::{self, Command};
+use askme::exam::{Exam, ExamItem, DEFAULT_PASS_MARK};
+use askme::grading::{
+    grade_answer, grade_list, grade_order, parse_order, split_list_answer, ListGrade, OrderInput,
+    OrderScoring,
+};
+use askme::mastery::run_until_mastered;
 use askme::prelude::*;
-use askme::{get_input, shuffle_arr, wait_for};
+use askme::scoring::{Scoring, Summary};
+use askme::selector::AdaptiveSelector;
+use askme::timing::Clock;
+use askme::ui::{self, Span, Tone};
+use askme::{
+    get_input_until, get_list_input, get_optional_input, get_select_input, print_response_times,
+    print_warning, shuffle_arr, wait_for,
+};
 use colored::Colorize;
 use figlet_rs::FIGfont;
+use std::time::Instant;
 
 const CORRECT_FEEDBACK_STR: &str = "✔️ That's correct!";
 const INCORRECT_FEEDBACK_STR: &str = "❌ Not quite correct..";
+const PARTIAL_FEEDBACK_STR: &str = "➖ Partially correct";
 
 pub struct Settings {
     pub shuffle: bool,
@@ -26,79 +42,332 @@ pub struct Settings {
     pub case_sensitive: bool,
     pub show_correct: bool,
     pub wait_duration: f64,
+    pub order_scoring: OrderScoring,
+    pub order_input: OrderInput,
+    pub until_mastered: Option<u32>,
+    pub adaptive: bool,
+    pub count: Option<usize>,
+    pub question_time: Option<f64>,
+    pub time_limit: Option<f64>,
+    pub exam: bool,
+    pub pass_mark: Option<f64>,
+    pub scoring: Scoring,
 }
 
 pub struct App {
     set: AskmeSet,
     settings: Settings,
-    correct_count: i32,
+    asked_count: usize,
+    clock: Clock,
+    results: Vec<QuestionResult>,
 }
 
 impl App {
-    fn check_answer(&self, question: &Question, user_answer: String) -> bool {
-        match self.settings.case_sensitive {
-            true => question.answers.contains(&user_answer.trim().to_string()),
-            false => {
-                let questions_lowercase = &question
-                    .answers
-                    .iter()
-                    .map(|a| a.to_lowercase())
-                    .collect::<Vec<String>>();
-
-                questions_lowercase.contains(&user_answer.trim().to_string())
-            }
-        }
-    }
-
     pub fn print_question_answers(&self, question: &Question) {
         let answers = &question.answers;
 
         if answers.is_empty() {
-            println!("The correct answer is: {}\n", answers[0].bold());
+            ui::print_spans(&[
+                Span::plain("The correct answer is: "),
+                Span::new(Tone::Bold, &answers[0]),
+            ]);
+            ui::print(Tone::Plain, "");
             return;
         }
 
         let ans_text = answers.join(", ");
-        println!("{}{}", "The Correct answers are: ".bold(), ans_text);
+        ui::print_labelled("The Correct answers are: ", &ans_text);
     }
 
-    fn provide_qn_feedback(&self, question: &Question, correct: bool) {
-        match correct {
-            true => println!("{}\n", CORRECT_FEEDBACK_STR.green()),
-            false => {
-                println!("{}\n", INCORRECT_FEEDBACK_STR.red());
+    fn provide_qn_feedback(&self, question: &Question, score: f64) {
+        match score {
+            s if s >= 1.0 => return ui::print_spaced(Tone::Correct, CORRECT_FEEDBACK_STR),
+            s if s > 0.0 => ui::print_spaced(Tone::Partial, PARTIAL_FEEDBACK_STR),
+            _ => ui::print_spaced(Tone::Incorrect, INCORRECT_FEEDBACK_STR),
+        };
+
+        if self.settings.show_correct {
+            self.print_question_answers(question);
+        }
+    }
 
-                if self.settings.show_correct {
-                    self.print_question_answers(question);
+    fn provide_list_feedback(&self, question: &Question, grade: &ListGrade) {
+        if grade.is_correct() {
+            ui::print_spaced(Tone::Correct, CORRECT_FEEDBACK_STR);
+            return;
+        }
+
+        match grade.matched.is_empty() {
+            true => ui::print_spaced(Tone::Incorrect, INCORRECT_FEEDBACK_STR),
+            false => ui::print_spans(&[
+                Span::new(Tone::Partial, PARTIAL_FEEDBACK_STR),
+                Span::plain(format!(
+                    " ({}/{})",
+                    grade.matched.len(),
+                    question.answers.len()
+                )),
+            ]),
+        }
+
+        if !grade.missed.is_empty() {
+            ui::print_labelled("Missed: ", &grade.missed.join(", "));
+        }
+        if !grade.extra.is_empty() {
+            ui::print_labelled("Not part of the answer: ", &grade.extra.join(", "));
+        }
+        if self.settings.show_correct {
+            self.print_question_answers(question);
+        }
+        ui::print(Tone::Plain, "");
+    }
+
+    /// Read the order of `items` from the user, as indices into `items`.
+    /// Returns `None` if the time ran out or a command ended the prompt.
+    fn get_order_input(&self, items: &[String], deadline: Option<Instant>) -> Option<Vec<usize>> {
+        // Picking from a list can't time out, so timed questions are typed
+        let letters =
+            matches!(self.settings.order_input, OrderInput::Letters) || deadline.is_some();
+
+        match letters && items.len() <= 26 {
+            true => loop {
+                if let Some(order) = parse_order(&get_input_until(deadline)?, items.len()) {
+                    break Some(order);
                 }
+                print_warning("enter each letter exactly once, e.g. \"bca\"");
+            },
+            _ => {
+                let mut remaining = (0..items.len()).collect::<Vec<usize>>();
+                let mut order = Vec::new();
+
+                while !remaining.is_empty() {
+                    let options = remaining.iter().map(|&i| items[i].clone()).collect();
+                    let message = format!("Item {}/{}:", order.len() + 1, items.len());
+                    order.push(remaining.remove(get_select_input(&message, options)?));
+                }
+
+                Some(order)
             }
-        };
+        }
+    }
+
+    fn provide_order_feedback(&self, question: &Question, score: f64) {
+        match score {
+            s if s >= 1.0 => ui::print_spaced(Tone::Correct, CORRECT_FEEDBACK_STR),
+            s if s > 0.0 => ui::print_spaced(Tone::Partial, PARTIAL_FEEDBACK_STR),
+            _ => ui::print_spaced(Tone::Incorrect, INCORRECT_FEEDBACK_STR),
+        }
+
+        if score < 1.0 && self.settings.show_correct {
+            ui::print_labelled("The correct order is: ", &question.answers.join(" → "));
+            ui::print(Tone::Plain, "");
+        }
+    }
+
+    /// Grade an answer given without feedback. `shown` holds the correct
+    /// positions of an ordering question's items, in the order they were shown.
+    fn grade_answer(&self, question: &Question, shown: &[usize], answer: &str) -> f64 {
+        let case_sensitive = self.settings.case_sensitive;
+
+        match question.kind {
+            QuestionKind::Single => grade_answer(
+                question,
+                answer,
+                case_sensitive,
+                self.settings.scoring.typo_credit,
+            ),
+            QuestionKind::List => {
+                grade_list(question, &split_list_answer(answer), case_sensitive).score
+            }
+            QuestionKind::Order => match parse_order(answer, shown.len()) {
+                Some(order) => {
+                    let given = order.iter().map(|&i| shown[i]).collect::<Vec<usize>>();
+                    grade_order(&given, self.settings.order_scoring)
+                }
+                None => 0.0,
+            },
+        }
+    }
+
+    /// Ask every question without feedback, then review the submitted answers.
+    fn run_exam(&mut self, questions: Vec<Question>) {
+        // Ordering questions keep their shuffled order when revisited
+        let shuffles = questions
+            .iter()
+            .map(|qn| shuffle_arr(&(0..qn.answers.len()).collect::<Vec<usize>>()))
+            .collect::<Vec<Vec<usize>>>();
+
+        let items = questions
+            .iter()
+            .map(|qn| ExamItem {
+                title: qn.title.clone(),
+                correct: match qn.kind {
+                    QuestionKind::Order => qn.answers.join(" → "),
+                    _ => qn.answers.join(", "),
+                },
+                points: qn.points,
+            })
+            .collect();
+        let mut exam = Exam::new(items);
+
+        while let Some(idx) = exam.next_question() {
+            if self.clock.is_over() {
+                break;
+            }
+
+            let question = &questions[idx];
+            ui::show_question(&question.title);
+            if question.kind == QuestionKind::Order {
+                let items = shuffles[idx]
+                    .iter()
+                    .map(|&pos| question.answers[pos].clone())
+                    .collect::<Vec<String>>();
+                ui::print_options(&items);
+            }
+
+            exam.answer(idx, get_optional_input(self.clock.question_deadline()));
+            ui::print(Tone::Plain, "");
+        }
+
+        let scores = questions
+            .iter()
+            .zip(exam.answers())
+            .zip(&shuffles)
+            .map(|((question, answer), shown)| match answer {
+                Some(answer) => self.grade_answer(question, shown, answer),
+                None => 0.0,
+            })
+            .collect::<Vec<f64>>();
+
+        let points = questions
+            .iter()
+            .zip(&scores)
+            .map(|(question, &score)| self.settings.scoring.points(question, score, false))
+            .collect::<Vec<f64>>();
+        let results = exam.results(&scores, &points);
+
+        exam.print_review(
+            &results,
+            self.settings.pass_mark.unwrap_or(DEFAULT_PASS_MARK),
+        );
+
+        self.asked_count += questions.len();
+        for result in results {
+            ui::show_result(&result);
+            self.results.push(result);
+        }
+    }
+
+    /// Forget the last answer, as if its question hadn't been asked.
+    fn undo(&mut self) {
+        if commands::undo_last(&mut self.results).is_some() {
+            self.asked_count -= 1;
+        }
     }
 
-    fn ask_question(&mut self, question: &Question) {
-        println!(" {}", question.title.bold());
+    /// Ask a question, returning the score earned for it, or `None` if the
+    /// session time ran out (or the user quit) before it could be asked.
+    fn ask_question(&mut self, question: &Question) -> Option<f64> {
+        if self.clock.is_over() || commands::has_quit() {
+            return None;
+        }
+
+        commands::begin_question(std::slice::from_ref(question), &self.results);
+        ui::show_question(&question.title);
+
+        let deadline = self.clock.question_deadline();
+        let started = Instant::now();
+
+        let (answer, score) = match question.kind {
+            QuestionKind::Single => match get_input_until(deadline) {
+                Some(answer) => {
+                    let score = grade_answer(
+                        question,
+                        &answer,
+                        self.settings.case_sensitive,
+                        self.settings.scoring.typo_credit,
+                    );
+                    self.provide_qn_feedback(question, score);
+
+                    (Some(answer), score)
+                }
+                None => (None, 0.0),
+            },
+            QuestionKind::List => match get_list_input(question.answers.len(), deadline) {
+                Some(items) => {
+                    let grade = grade_list(question, &items, self.settings.case_sensitive);
+                    self.provide_list_feedback(question, &grade);
+
+                    (Some(items.join(", ")), grade.score)
+                }
+                None => (None, 0.0),
+            },
+            QuestionKind::Order => {
+                // Correct positions of the items, in the order they are shown
+                let shown = shuffle_arr(&(0..question.answers.len()).collect::<Vec<usize>>());
+                let items = shown
+                    .iter()
+                    .map(|&pos| question.answers[pos].clone())
+                    .collect::<Vec<String>>();
+
+                ui::print_options(&items);
+
+                match self.get_order_input(&items, deadline) {
+                    Some(order) => {
+                        let given = order.iter().map(|&i| shown[i]).collect::<Vec<usize>>();
+                        let score = grade_order(&given, self.settings.order_scoring);
+                        self.provide_order_feedback(question, score);
 
-        let user_answer = match self.settings.case_sensitive {
-            true => get_input(),
-            false => get_input().to_lowercase(),
+                        let answer = order.iter().map(|&i| items[i].clone()).collect::<Vec<_>>();
+                        (Some(answer.join(" → ")), score)
+                    }
+                    None => (None, 0.0),
+                }
+            }
         };
 
-        let correct = self.check_answer(question, user_answer);
-        self.provide_qn_feedback(question, correct);
+        // Without an answer, either the time ran out or a command ended the prompt
+        let interrupt = commands::interrupt_of(&answer);
+        match interrupt {
+            Some(Command::Quit) => return None,
+            Some(Command::Undo) => {
+                self.undo();
+                return self.ask_question(question);
+            }
+            Some(_) => (), // Skipped and revealed questions count as wrong
+            None if answer.is_none() => self.provide_qn_feedback(question, 0.0),
+            None => (),
+        }
+
+        // Hints taken cost part of the credit
+        let score = self
+            .settings
+            .scoring
+            .after_hints(score, commands::hints_taken());
 
-        match correct {
-            true => self.correct_count += 1,
-            false => (),
+        self.asked_count += 1;
+        let result = QuestionResult {
+            title: question.title.clone(),
+            timed_out: answer.is_none() && interrupt.is_none(),
+            answer,
+            score,
+            points: self.settings.scoring.points(question, score, false),
+            max_points: question.points,
+            response_time: started.elapsed(),
         };
+        ui::show_result(&result);
+        self.results.push(result);
 
         wait_for(self.settings.wait_duration);
+        Some(score)
     }
 }
 
-impl AskmeMode<Settings, i32> for App {
+impl AskmeMode<Settings, Summary> for App {
     fn new(set: AskmeSet, settings: Settings) -> Self {
         App {
-            correct_count: 0,
+            asked_count: 0,
+            clock: Clock::new(settings.question_time, settings.time_limit),
+            results: Vec::new(),
             set,
             settings,
         }
@@ -117,30 +386,70 @@ impl AskmeMode<Settings, i32> for App {
         format!(" {}\n", self.set.subtitle.blue())
     }
 
+    fn get_asked_count(&self) -> usize {
+        self.asked_count
+    }
+
+    fn get_set(&self) -> &AskmeSet {
+        &self.set
+    }
+
+    fn get_results(&self) -> &[QuestionResult] {
+        &self.results
+    }
+
     fn run_set(&mut self) {
         let qns = match self.settings.shuffle {
             false => self.set.questions.clone(),
             true => shuffle_arr(&self.set.questions.clone()),
         };
 
-        qns.iter().for_each(|question| self.ask_question(question));
+        if self.settings.exam {
+            return self.run_exam(qns);
+        }
+
+        if self.settings.adaptive {
+            let count = self.settings.count.unwrap_or(qns.len());
+            let mut selector = AdaptiveSelector::from_history(qns, &self.results);
+            return selector.run(count, |question| self.ask_question(question));
+        }
+
+        match self.settings.until_mastered {
+            Some(streak) => run_until_mastered(qns, streak, |question| {
+                self.ask_question(question).map(|score| score >= 1.0)
+            }),
+            None => {
+                for (asked, question) in qns.iter().enumerate() {
+                    ui::show_progress(asked, qns.len());
+                    if self.ask_question(question).is_none() {
+                        break;
+                    }
+                }
+            }
+        }
     }
 
-    fn run(&mut self) -> Result<i32, &str> {
+    fn run(&mut self) -> Result<Summary, AskmeError> {
         if self.set.questions.is_empty() {
-            return Err("no questions provided!");
+            return Err(AskmeError::new("no questions provided!", None));
         };
 
-        println!("{}", self.get_title());
-        println!("{}", self.get_subtitle());
+        ui::show_title(&self.set.title, &self.set.subtitle);
 
+        self.clock.start();
         match self.settings.loop_questions {
-            true => loop {
-                self.run_set()
-            },
+            true => {
+                while !self.clock.is_over() && !commands::has_quit() {
+                    self.run_set()
+                }
+            }
             false => self.run_set(),
         }
 
-        Ok(self.correct_count)
+        if self.clock.is_limited() {
+            print_response_times(&self.results);
+        }
+
+        Ok(Summary::from_results(&self.results))
     }
 }
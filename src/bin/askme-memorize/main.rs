@@ -14,83 +14,196 @@
 
 use std::fmt::Debug;
 
-use askme::prelude::*;
-use askme::print_correct_answers;
+use askme::cli::{self, CommonArgs, Mode};
+use askme::config::Options;
+use askme::grading::{OrderInput, OrderScoring};
+use askme::scoring::Scoring;
 
 mod app;
 
+const MODE: Mode = Mode {
+    name: "memorize",
+    single_only: false,
+    adaptive: true,
+};
+
 use app::App;
 use clap::Parser;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    filename: String,
+    #[command(flatten)]
+    common: CommonArgs,
+
+    #[arg(
+        long,
+        value_name = "STREAK",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "1",
+        conflicts_with_all = ["loop_questions", "exam"],
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Re-ask missed questions until each is answered correctly STREAK times in a row"
+    )]
+    until_mastered: Option<u32>,
 
     #[arg(
-        default_value_t = 1.0,
-        long = "duration",
-        short = 'd',
-        help = "Choose how long the delay is between new questions (seconds, can be decimal)"
+        long,
+        short = 'A',
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        conflicts_with_all = ["until_mastered", "exam"],
+        help = "Pick questions by their weight and how often they were missed"
     )]
-    wait_duration: f64,
+    adaptive: Option<bool>,
 
     #[arg(
         long,
-        short = 'L',
-        help = "Choose if you would like to be quizzed in a loop"
+        short = 'c',
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Only ask N questions, drawn at random from the set (with --adaptive: per pass)"
     )]
-    loop_questions: bool,
+    count: Option<u64>,
 
-    #[arg(long, short = 's', help = "Enable shuffling")]
-    shuffle: bool,
+    #[arg(
+        long,
+        short = 's',
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        help = "Enable shuffling"
+    )]
+    shuffle: Option<bool>,
 
     #[arg(
         long,
         short = 'C',
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
         help = "Make answers to the questions case-sensitive"
     )]
-    case_sensitive: bool,
+    case_sensitive: Option<bool>,
 
     #[arg(
         long,
         short = 'S',
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
         help = "show the correct answer(s) if an answer was wrong"
     )]
-    show_correct: bool,
+    show_correct: Option<bool>,
+
+    #[arg(
+        long,
+        short = 't',
+        value_name = "SECONDS",
+        help = "Time limit for answering each question"
+    )]
+    question_time: Option<f64>,
+
+    #[arg(
+        long,
+        short = 'T',
+        value_name = "SECONDS",
+        help = "Time limit for the whole session"
+    )]
+    time_limit: Option<f64>,
+
+    #[arg(
+        long,
+        short = 'e',
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        conflicts_with = "loop_questions",
+        help = "Exam mode: no feedback until all answers are submitted"
+    )]
+    exam: Option<bool>,
+
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        help = "Percentage needed to pass the exam [default: 50]"
+    )]
+    pass_mark: Option<f64>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "How ordering questions are scored [default: position]"
+    )]
+    order_scoring: Option<OrderScoring>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "How ordering questions are answered [default: letters]"
+    )]
+    order_input: Option<OrderInput>,
+
+    #[arg(
+        long,
+        value_name = "FRACTION",
+        help = "Credit for an answer that is only off by a typo [default: 0]"
+    )]
+    typo_credit: Option<f64>,
+}
+
+impl Args {
+    /// The settings given on the command line.
+    fn options(&self) -> Options {
+        Options {
+            shuffle: self.shuffle,
+            case_sensitive: self.case_sensitive,
+            show_correct: self.show_correct,
+            order_scoring: self.order_scoring,
+            order_input: self.order_input,
+            until_mastered: self.until_mastered,
+            adaptive: self.adaptive,
+            count: self.count.map(|count| count as usize),
+            question_time: self.question_time,
+            time_limit: self.time_limit,
+            exam: self.exam,
+            pass_mark: self.pass_mark,
+            typo_credit: self.typo_credit,
+            ..self.common.options()
+        }
+    }
 }
 
-impl From<Args> for app::Settings {
-    fn from(val: Args) -> Self {
+impl From<Options> for app::Settings {
+    fn from(val: Options) -> Self {
         app::Settings {
-            shuffle: val.shuffle,
-            loop_questions: val.loop_questions,
-            case_sensitive: val.case_sensitive,
-            show_correct: val.show_correct,
-            wait_duration: val.wait_duration,
+            shuffle: val.shuffle.unwrap_or_default(),
+            loop_questions: val.loop_questions.unwrap_or_default(),
+            case_sensitive: val.case_sensitive.unwrap_or_default(),
+            show_correct: val.show_correct.unwrap_or_default(),
+            wait_duration: val.wait_duration.unwrap_or(1.0),
+            order_scoring: val.order_scoring.unwrap_or(OrderScoring::Position),
+            order_input: val.order_input.unwrap_or(OrderInput::Letters),
+            until_mastered: val.until_mastered,
+            adaptive: val.adaptive.unwrap_or_default(),
+            count: val.count,
+            question_time: val.question_time,
+            time_limit: val.time_limit,
+            exam: val.exam.unwrap_or_default(),
+            pass_mark: val.pass_mark,
+            scoring: Scoring::from(&val),
         }
     }
 }
 
 fn main() {
     let args = Args::parse();
-
-    let set = match AskmeSet::from_file(&args.filename) {
-        Ok(s) => s,
-        Err(e) => {
-            askme::print_err(&format!("{}", e));
-            std::process::exit(1)
-        }
-    };
-
-    let set_questions = set.questions.len();
-
-    let mut app = App::new(set, app::Settings::from(args));
-
-    let correct_count = match app.run() {
-        Ok(c) => c,
-        Err(e) => panic!("error: {}", e),
-    };
-
-    print_correct_answers(correct_count, set_questions);
+    cli::run::<App, app::Settings>(&MODE, &args.common, &args.options());
 }
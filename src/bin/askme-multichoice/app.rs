@@ -12,140 +12,359 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use askme::{prelude::*, print_warning, wait_for};
+use askme::commands::{self, Command};
+use askme::exam::{Exam, ExamItem, DEFAULT_PASS_MARK};
+use askme::generate::{
+    choices_available, multiple_choices, multiple_select_choices, select_choices_available,
+    warn_few_choices, Distractors,
+};
+use askme::grading::{grade_selection, option_labels, parse_option, parse_options, SelectScoring};
+use askme::mastery::run_until_mastered;
+use askme::scoring::{Scoring, Summary};
+use askme::selector::AdaptiveSelector;
+use askme::timing::Clock;
+use askme::ui::{self, Span, Tone};
+use askme::{
+    get_input_until, get_labelled_select_input, get_multi_select_input, get_optional_input,
+    prelude::*, print_response_times, print_warning, wait_for,
+};
 use colored::Colorize;
 use figlet_rs::FIGfont;
-use inquire::{InquireError, Select};
-use rand::Rng;
+use std::time::Instant;
 
 const CORRECT_FEEDBACK_STR: &str = "✔️ That's correct!";
 const INCORRECT_FEEDBACK_STR: &str = "❌ Not quite correct..";
-
-static MCQ_LETTERS: [char; 26] = [
-    'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's',
-    't', 'u', 'v', 'w', 'x', 'y', 'z',
-];
+const PARTIAL_FEEDBACK_STR: &str = "➖ Partially correct";
 
 pub struct Settings {
     pub loop_questions: bool,
     pub show_correct: bool,
     pub wait_duration: f64,
     pub max_choices: usize,
+    pub select_all: bool,
+    pub select_scoring: SelectScoring,
+    pub distractors: Distractors,
+    pub until_mastered: Option<u32>,
+    pub adaptive: bool,
+    pub count: Option<usize>,
+    pub question_time: Option<f64>,
+    pub time_limit: Option<f64>,
+    pub exam: bool,
+    pub pass_mark: Option<f64>,
+    pub scoring: Scoring,
+}
+
+/// The options shown for a question.
+struct Choices {
+    options: Vec<String>, // Options in the order they are shown
+    correct: Vec<usize>,  // Indices of the correct options
+    select_all: bool,     // Whether every correct option must be picked
+}
+
+impl Choices {
+    /// The picked options, as the answer to record.
+    fn answer(&self, picked: &[usize]) -> String {
+        picked
+            .iter()
+            .map(|&idx| self.options[idx].as_str())
+            .collect::<Vec<&str>>()
+            .join(", ")
+    }
 }
 
 pub struct App {
     set: AskmeSet,
     settings: Settings,
-    correct_count: i32,
+    asked_count: usize,
+    clock: Clock,
+    results: Vec<QuestionResult>,
 }
 
 impl App {
-    /// Randomly generate a list of answers based on the set answers.
-    pub fn aggregate_answers(
+    fn provide_qn_feedback(&self, score: f64, choices: &Choices) {
+        match score {
+            s if s >= 1.0 => return ui::print_spaced(Tone::Correct, CORRECT_FEEDBACK_STR),
+            s if s > 0.0 => ui::print_spaced(Tone::Partial, PARTIAL_FEEDBACK_STR),
+            _ => ui::print_spaced(Tone::Incorrect, INCORRECT_FEEDBACK_STR),
+        }
+
+        if self.settings.show_correct {
+            let labels = option_labels(choices.options.len());
+            let correct = choices
+                .correct
+                .iter()
+                .map(|&idx| format!("{}) {}", labels[idx], choices.options[idx]))
+                .collect::<Vec<String>>();
+            let message = match correct.len() {
+                1 => "The correct option is: ",
+                _ => "The correct options are: ",
+            };
+            ui::print_spans(&[
+                Span::new(Tone::Incorrect, message),
+                Span::new(Tone::Bold, correct.join(", ")),
+            ])
+        }
+    }
+
+    /// Generate the options for `question`.
+    fn choices(&self, question: &Question) -> Choices {
+        let select_all = question.select_all.unwrap_or(self.settings.select_all);
+        let max_choices = self.settings.max_choices;
+        let distractors = self.settings.distractors;
+
+        let available = match select_all {
+            true => select_choices_available(question, &self.set.all_questions),
+            false => choices_available(question, &self.set.all_questions),
+        };
+        warn_few_choices(question, available, max_choices.max(1));
+
+        let (options, correct) = match select_all {
+            true => {
+                multiple_select_choices(question, &self.set.all_questions, max_choices, distractors)
+            }
+            false => {
+                let (options, correct) =
+                    multiple_choices(question, &self.set.all_questions, max_choices, distractors);
+                (options, vec![correct])
+            }
+        };
+
+        Choices {
+            options,
+            correct,
+            select_all,
+        }
+    }
+
+    /// Credit for picking `picked` out of `choices`.
+    fn grade(&self, choices: &Choices, picked: &[usize]) -> f64 {
+        match choices.select_all {
+            true => grade_selection(picked, &choices.correct, self.settings.select_scoring),
+            false => match picked == choices.correct.as_slice() {
+                true => 1.0,
+                false => 0.0,
+            },
+        }
+    }
+
+    /// Let the user pick the options of `choices` (one of them, unless all
+    /// that apply are asked for), returning their indices, or `None` if the
+    /// time ran out. If `skippable`, picking nothing skips the question,
+    /// which also returns `None`.
+    fn get_picks(
         &self,
-        curr_question: &Question,
-        questions: &[Question],
-        mut len: usize,
-    ) -> (Vec<String>, usize) {
-        let mut choices_len = 0;
-        for ele in questions {
-            choices_len += ele.answers.len();
-        }
-
-        if choices_len < len {
-            print_warning(&format!(
-                "Not enough answers to aggregate! Reducing maximum choices to {}",
-                choices_len
-            ));
-            len = choices_len
-        }
-
-        let mut rng = rand::thread_rng();
-
-        // Tuple of question idx and answer idx
-        let mut added_index = Vec::new();
-
-        let correct_answer = &curr_question.answers[rng.gen_range(0..curr_question.answers.len())];
-
-        let mut wrong_answers = (0..len - 1)
-            .map(|_| loop {
-                let idx = rng.gen_range(0..questions.len());
-                let answers_len = questions[idx].answers.len();
-                let answers_idx = rng.gen_range(0..answers_len);
-                if !(added_index.contains(&(idx, answers_idx))
-                    || self.set.questions[idx].answers[answers_idx] == *correct_answer)
-                {
-                    added_index.push((idx, answers_idx));
-                    break questions[idx].answers[answers_idx].clone();
-                }
+        choices: &Choices,
+        deadline: Option<Instant>,
+        skippable: bool,
+    ) -> Option<Vec<usize>> {
+        if !choices.select_all {
+            return self
+                .get_choice(&choices.options, deadline, skippable)
+                .map(|idx| vec![idx]);
+        }
+
+        // A menu can't time out, so timed questions are typed
+        if deadline.is_none() && ui::has_menus() {
+            let picked =
+                get_multi_select_input("Answer (pick all that apply):", choices.options.clone())?;
+            return (!(skippable && picked.is_empty())).then_some(picked);
+        }
+
+        let example = match choices.options.len() <= 26 {
+            true => "ac",
+            false => "1, 3",
+        };
+        ui::print_options(&choices.options);
+        ui::print(
+            Tone::Plain,
+            &format!("Pick all that apply, e.g. \"{}\"", example),
+        );
+
+        loop {
+            let input = match skippable {
+                true => get_optional_input(deadline)?,
+                false => get_input_until(deadline)?,
+            };
+
+            if let Some(picked) = parse_options(&input, choices.options.len()) {
+                break Some(picked);
+            }
+            print_warning("enter the letters of the options that apply");
+        }
+    }
+
+    /// Let the user pick one of `options`, by its letter or from a menu,
+    /// returning its index, or `None` if the time ran out. If `skippable`,
+    /// the question can be skipped, which also returns `None`.
+    fn get_choice(
+        &self,
+        options: &[String],
+        deadline: Option<Instant>,
+        skippable: bool,
+    ) -> Option<usize> {
+        // A menu can't time out, so timed questions are typed
+        if deadline.is_none() && ui::has_menus() {
+            let mut menu = options.to_vec();
+            if skippable {
+                menu.push("(skip)".to_string());
+            }
+
+            let idx = get_labelled_select_input("Answer:", menu)?;
+            return (idx < options.len()).then_some(idx);
+        }
+
+        ui::print_options(options);
+
+        loop {
+            let input = match skippable {
+                true => get_optional_input(deadline)?,
+                false => get_input_until(deadline)?,
+            };
+
+            if let Some(idx) = parse_option(&input, options) {
+                break Some(idx);
+            }
+            print_warning("enter the letter of one of the options");
+        }
+    }
+
+    /// Ask every question without feedback, then review the submitted answers.
+    fn run_exam(&mut self, questions: Vec<Question>) {
+        // The choices stay the same when a question is revisited
+        let choices = questions
+            .iter()
+            .map(|qn| self.choices(qn))
+            .collect::<Vec<Choices>>();
+
+        let items = questions
+            .iter()
+            .zip(&choices)
+            .map(|(qn, choices)| ExamItem {
+                title: qn.title.clone(),
+                correct: choices.answer(&choices.correct),
+                points: qn.points,
             })
-            .collect::<Vec<String>>();
+            .collect();
+        let mut exam = Exam::new(items);
+        let mut picks = vec![None; questions.len()];
 
-        let correct_answer_location = rng.gen_range(0..len);
+        while let Some(idx) = exam.next_question() {
+            if self.clock.is_over() {
+                break;
+            }
+
+            ui::show_question(&questions[idx].title);
+            let picked = self.get_picks(&choices[idx], self.clock.question_deadline(), true);
 
-        let curr_question_correct_answer_location = rng.gen_range(0..curr_question.answers.len());
+            exam.answer(idx, picked.as_ref().map(|p| choices[idx].answer(p)));
+            picks[idx] = picked;
+            ui::print(Tone::Plain, "");
+        }
+
+        let scores = picks
+            .iter()
+            .zip(&choices)
+            .map(|(picked, choices)| match picked {
+                Some(picked) => self.grade(choices, picked),
+                None => 0.0,
+            })
+            .collect::<Vec<f64>>();
+
+        // Skipped questions cost nothing, wrong choices may
+        let points = questions
+            .iter()
+            .zip(&picks)
+            .zip(&scores)
+            .map(|((question, picked), &score)| {
+                let wrong_choice = picked.is_some() && score == 0.0;
+                self.settings.scoring.points(question, score, wrong_choice)
+            })
+            .collect::<Vec<f64>>();
+        let results = exam.results(&scores, &points);
 
-        wrong_answers.insert(
-            correct_answer_location,
-            curr_question.answers[curr_question_correct_answer_location].clone(),
+        exam.print_review(
+            &results,
+            self.settings.pass_mark.unwrap_or(DEFAULT_PASS_MARK),
         );
 
-        // now wrong_answers now contains the correct answer
-        (wrong_answers, correct_answer_location)
+        self.asked_count += questions.len();
+        for result in results {
+            ui::show_result(&result);
+            self.results.push(result);
+        }
     }
 
-    pub fn provide_qn_feedback(&self, correct: bool, correct_choice_index: usize) {
-        match correct {
-            true => println!("{}\n", CORRECT_FEEDBACK_STR.green()),
-            false => {
-                println!("{}\n", INCORRECT_FEEDBACK_STR.red());
-
-                if self.settings.show_correct {
-                    println!(
-                        "{}",
-                        format!(
-                            "The correct option is: {}",
-                            MCQ_LETTERS[correct_choice_index].to_string().bold() // place the letter inside
-                        )
-                        .red()
-                    )
-                }
-            }
+    /// Forget the last answer, as if its question hadn't been asked.
+    fn undo(&mut self) {
+        if commands::undo_last(&mut self.results).is_some() {
+            self.asked_count -= 1;
         }
     }
 
-    pub fn ask_question(&mut self, question: &Question) {
-        println!(" {}", question.title.bold());
+    /// Ask a question, returning whether it was answered correctly, or `None`
+    /// if the session time ran out (or the user quit) before it could be asked.
+    pub fn ask_question(&mut self, question: &Question) -> Option<bool> {
+        if self.clock.is_over() || commands::has_quit() {
+            return None;
+        }
 
-        let available_answers =
-            self.aggregate_answers(question, &self.set.questions, self.settings.max_choices);
+        commands::begin_question(std::slice::from_ref(question), &self.results);
+        ui::show_question(&question.title);
 
-        let user_answer = match Select::new("Answer:", available_answers.0.clone()).prompt() {
-            Ok(answer) => answer,
-            Err(err) => {
-                if let InquireError::OperationInterrupted = err {
-                    std::process::exit(1)
-                } else {
-                    panic!("Failed reading from stdin!")
-                }
-            }
-        };
+        let choices = self.choices(question);
 
-        let is_correct = available_answers.0[available_answers.1] == user_answer;
+        let started = Instant::now();
+        let picked = self.get_picks(&choices, self.clock.question_deadline(), false);
 
-        if is_correct {
-            self.correct_count += 1;
+        // Without an answer, either the time ran out or a command ended the prompt
+        let interrupt = commands::interrupt_of(&picked);
+        match interrupt {
+            Some(Command::Quit) => return None,
+            Some(Command::Undo) => {
+                self.undo();
+                return self.ask_question(question);
+            }
+            _ => (), // Skipped and revealed questions count as wrong
         }
 
-        self.provide_qn_feedback(is_correct, available_answers.1);
+        // Timed out questions count as wrong, but only wrong choices may cost points
+        let credit = match &picked {
+            Some(picked) => self.grade(&choices, picked),
+            None => 0.0,
+        };
+        let wrong_choice = picked.is_some() && credit == 0.0;
+        let score = self
+            .settings
+            .scoring
+            .after_hints(credit, commands::hints_taken());
+
+        self.asked_count += 1;
+        let result = QuestionResult {
+            title: question.title.clone(),
+            timed_out: picked.is_none() && interrupt.is_none(),
+            answer: picked.map(|picked| choices.answer(&picked)),
+            score,
+            points: self.settings.scoring.points(question, score, wrong_choice),
+            max_points: question.points,
+            response_time: started.elapsed(),
+        };
+        ui::show_result(&result);
+        self.results.push(result);
+
+        if interrupt.is_none() {
+            self.provide_qn_feedback(credit, &choices);
+        }
         wait_for(self.settings.wait_duration);
+        Some(credit >= 1.0)
     }
 }
 
-impl AskmeMode<Settings, i32> for App {
+impl AskmeMode<Settings, Summary> for App {
     fn new(set: AskmeSet, settings: Settings) -> Self {
         App {
-            correct_count: 0,
+            asked_count: 0,
+            clock: Clock::new(settings.question_time, settings.time_limit),
+            results: Vec::new(),
             set,
             settings,
         }
@@ -162,27 +381,68 @@ impl AskmeMode<Settings, i32> for App {
         format!(" {}\n", self.set.subtitle.blue())
     }
 
+    fn get_asked_count(&self) -> usize {
+        self.asked_count
+    }
+
+    fn get_set(&self) -> &AskmeSet {
+        &self.set
+    }
+
+    fn get_results(&self) -> &[QuestionResult] {
+        &self.results
+    }
+
     fn run_set(&mut self) {
         let qns = self.set.questions.clone();
 
-        qns.iter().for_each(|question| self.ask_question(question));
+        if self.settings.exam {
+            return self.run_exam(qns);
+        }
+
+        if self.settings.adaptive {
+            let count = self.settings.count.unwrap_or(qns.len());
+            let mut selector = AdaptiveSelector::from_history(qns, &self.results);
+            return selector.run(count, |question| {
+                self.ask_question(question)
+                    .map(|correct| if correct { 1.0 } else { 0.0 })
+            });
+        }
+
+        match self.settings.until_mastered {
+            Some(streak) => run_until_mastered(qns, streak, |question| self.ask_question(question)),
+            None => {
+                for (asked, question) in qns.iter().enumerate() {
+                    ui::show_progress(asked, qns.len());
+                    if self.ask_question(question).is_none() {
+                        break;
+                    }
+                }
+            }
+        }
     }
 
-    fn run(&mut self) -> Result<i32, &str> {
+    fn run(&mut self) -> Result<Summary, AskmeError> {
         if self.set.questions.is_empty() {
-            return Err("no questions provided!");
+            return Err(AskmeError::new("no questions provided!", None));
         };
 
-        println!("{}", self.get_title());
-        println!("{}", self.get_subtitle());
+        ui::show_title(&self.set.title, &self.set.subtitle);
 
+        self.clock.start();
         match self.settings.loop_questions {
-            true => loop {
-                self.run_set()
-            },
+            true => {
+                while !self.clock.is_over() && !commands::has_quit() {
+                    self.run_set()
+                }
+            }
             false => self.run_set(),
         }
 
-        Ok(self.correct_count)
+        if self.clock.is_limited() {
+            print_response_times(&self.results);
+        }
+
+        Ok(Summary::from_results(&self.results))
     }
 }
@@ -12,80 +12,192 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use askme::prelude::*;
-use askme::print_correct_answers;
+use askme::cli::{self, CommonArgs, Mode};
+use askme::config::Options;
+use askme::generate::Distractors;
+use askme::grading::SelectScoring;
+use askme::scoring::Scoring;
 
 mod app;
 
+const MODE: Mode = Mode {
+    name: "multichoice",
+    single_only: true,
+    adaptive: true,
+};
+
 use app::App;
 use clap::Parser;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    filename: String,
+    #[command(flatten)]
+    common: CommonArgs,
+
+    #[arg(
+        long,
+        value_name = "STREAK",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "1",
+        conflicts_with_all = ["loop_questions", "exam"],
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Re-ask missed questions until each is answered correctly STREAK times in a row"
+    )]
+    until_mastered: Option<u32>,
 
     #[arg(
-        default_value_t = 1.0,
-        long = "duration",
-        short = 'd',
-        help = "Choose how long the delay is between new questions (seconds, can be decimal)"
+        long,
+        short = 'A',
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        conflicts_with_all = ["until_mastered", "exam"],
+        help = "Pick questions by their weight and how often they were missed"
     )]
-    wait_duration: f64,
+    adaptive: Option<bool>,
 
     #[arg(
         long,
-        short = 'L',
-        help = "Choose if you would like to be quizzed in a loop"
+        short = 'c',
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Only ask N questions, drawn at random from the set (with --adaptive: per pass)"
     )]
-    loop_questions: bool,
+    count: Option<u64>,
 
     #[arg(
         long,
         short = 'C',
-        help = "Maximum choices for each question",
-        default_value_t = 4
+        help = "Maximum choices for each question [default: 4]"
+    )]
+    max_choices: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        help = "Ask to select all the options that apply, with several correct ones among them"
+    )]
+    select_all: Option<bool>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "How \"select all that apply\" questions are scored [default: partial]"
+    )]
+    select_scoring: Option<SelectScoring>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "How the wrong choices are picked [default: random]"
     )]
-    max_choices: usize,
+    distractors: Option<Distractors>,
 
     #[arg(
         long,
         short = 'S',
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
         help = "show the correct answer(s) if an answer was wrong"
     )]
-    show_correct: bool,
+    show_correct: Option<bool>,
+
+    #[arg(
+        long,
+        short = 't',
+        value_name = "SECONDS",
+        help = "Time limit for answering each question"
+    )]
+    question_time: Option<f64>,
+
+    #[arg(
+        long,
+        short = 'T',
+        value_name = "SECONDS",
+        help = "Time limit for the whole session"
+    )]
+    time_limit: Option<f64>,
+
+    #[arg(
+        long,
+        short = 'e',
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        conflicts_with = "loop_questions",
+        help = "Exam mode: no feedback until all answers are submitted"
+    )]
+    exam: Option<bool>,
+
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        help = "Percentage needed to pass the exam [default: 50]"
+    )]
+    pass_mark: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "FRACTION",
+        help = "Share of a question's points lost for each wrong choice [default: 0]"
+    )]
+    negative_marking: Option<f64>,
+}
+
+impl Args {
+    /// The settings given on the command line.
+    fn options(&self) -> Options {
+        Options {
+            show_correct: self.show_correct,
+            max_choices: self.max_choices,
+            select_all: self.select_all,
+            select_scoring: self.select_scoring,
+            distractors: self.distractors,
+            until_mastered: self.until_mastered,
+            adaptive: self.adaptive,
+            count: self.count.map(|count| count as usize),
+            question_time: self.question_time,
+            time_limit: self.time_limit,
+            exam: self.exam,
+            pass_mark: self.pass_mark,
+            negative_marking: self.negative_marking,
+            ..self.common.options()
+        }
+    }
 }
 
-impl From<Args> for app::Settings {
-    fn from(val: Args) -> Self {
+impl From<Options> for app::Settings {
+    fn from(val: Options) -> Self {
         app::Settings {
-            loop_questions: val.loop_questions,
-            show_correct: val.show_correct,
-            wait_duration: val.wait_duration,
-            max_choices: val.max_choices,
+            loop_questions: val.loop_questions.unwrap_or_default(),
+            show_correct: val.show_correct.unwrap_or_default(),
+            wait_duration: val.wait_duration.unwrap_or(1.0),
+            max_choices: val.max_choices.unwrap_or(4),
+            select_all: val.select_all.unwrap_or_default(),
+            select_scoring: val.select_scoring.unwrap_or(SelectScoring::Partial),
+            distractors: val.distractors.unwrap_or_default(),
+            until_mastered: val.until_mastered,
+            adaptive: val.adaptive.unwrap_or_default(),
+            count: val.count,
+            question_time: val.question_time,
+            time_limit: val.time_limit,
+            exam: val.exam.unwrap_or_default(),
+            pass_mark: val.pass_mark,
+            scoring: Scoring::from(&val),
         }
     }
 }
 
 fn main() {
     let args = Args::parse();
-
-    let set = match AskmeSet::from_file(&args.filename) {
-        Ok(s) => s,
-        Err(e) => {
-            askme::print_err(&format!("{}", e));
-            std::process::exit(1)
-        }
-    };
-
-    let set_questions = set.questions.len();
-
-    let mut app = App::new(set, app::Settings::from(args));
-
-    let correct_count = match app.run() {
-        Ok(c) => c,
-        Err(e) => panic!("error: {}", e),
-    };
-
-    print_correct_answers(correct_count, set_questions);
+    cli::run::<App, app::Settings>(&MODE, &args.common, &args.options());
 }
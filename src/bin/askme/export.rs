@@ -0,0 +1,258 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fs;
+use std::path::Path;
+
+use askme::generate::{distractor_candidates, Distractors};
+use askme::prelude::*;
+use askme::print_info;
+use askme::random::rng;
+use clap::{ArgGroup, Args};
+use rand::Rng;
+use serde_json::json;
+
+use crate::serve::STYLE;
+use crate::worksheet::{Format, Worksheet, WorksheetOptions};
+
+const QUIZ_TEMPLATE: &str = include_str!("quiz.html");
+
+#[derive(Args, Debug)]
+#[command(group(ArgGroup::new("format").required(true)))]
+pub struct ExportArgs {
+    #[arg(required = true, help = "Set files to export")]
+    filenames: Vec<String>,
+
+    #[arg(
+        long,
+        group = "format",
+        help = "Export a self-contained web page that runs the quiz"
+    )]
+    html: bool,
+
+    #[arg(long, group = "format", help = "Export a Markdown worksheet")]
+    markdown: bool,
+
+    #[arg(long, group = "format", help = "Export a plain text worksheet")]
+    text: bool,
+
+    #[arg(long, group = "format", help = "Export a LaTeX worksheet")]
+    latex: bool,
+
+    #[arg(
+        long,
+        short = 'o',
+        help = "File to write to [default: standard output]. Worksheets also get FILE-key for the answer key"
+    )]
+    output: Option<String>,
+
+    #[arg(
+        long,
+        conflicts_with = "output",
+        help = "Print the answer key instead of the worksheet (when writing to standard output)"
+    )]
+    key: bool,
+
+    #[arg(
+        long,
+        help = "Give lettered choices for single-answer questions on worksheets"
+    )]
+    multichoice: bool,
+
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u64).range(1..),
+        requires = "output",
+        help = "Number of worksheet versions, each with its own question order and choices"
+    )]
+    variants: u64,
+
+    #[arg(
+        long,
+        help = "Seed for the worksheet's question order and choices (version N uses SEED + N - 1)"
+    )]
+    seed: Option<u64>,
+
+    #[arg(
+        long,
+        short = 's',
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        help = "Shuffle the questions (every time the web quiz starts) [default: the set's, or false]"
+    )]
+    shuffle: Option<bool>,
+
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        help = "Make answers to the questions case-sensitive [default: the set's, or false]"
+    )]
+    case_sensitive: Option<bool>,
+
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u64).range(2..),
+        help = "Maximum choices for each multiple choice question [default: 4]"
+    )]
+    max_choices: Option<u64>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "How the wrong choices are picked [default: the set's, or random]"
+    )]
+    distractors: Option<Distractors>,
+}
+
+/// Render `set` into a web page running the quiz on its own, with the set
+/// embedded as JSON.
+fn export_html(set: &AskmeSet, args: &ExportArgs) -> String {
+    let settings = &set.settings;
+    let distractors = args
+        .distractors
+        .or(settings.distractors)
+        .unwrap_or_default();
+    let data = json!({
+        "title": set.title,
+        "subtitle": set.subtitle,
+        "shuffle": args.shuffle.or(settings.shuffle).unwrap_or_default(),
+        "case_sensitive": args.case_sensitive.or(settings.case_sensitive).unwrap_or_default(),
+        "max_choices": args
+            .max_choices
+            .map(|n| n as usize)
+            .or(settings.max_choices)
+            .unwrap_or(4),
+        "questions": set
+            .questions
+            .iter()
+            .map(|qn| {
+                let mut question = json!({
+                    "title": qn.title,
+                    "answers": qn.answers,
+                    "kind": qn.kind,
+                    "ordered": qn.ordered,
+                });
+                // The page ranks these the way `multiple_choices` does. Random
+                // picks need no ranking, and the page draws from every answer.
+                if distractors != Distractors::Random {
+                    question["distractors"] = json!(distractor_candidates(qn, &set.questions, distractors));
+                }
+                question
+            })
+            .collect::<Vec<_>>(),
+    });
+
+    // Keep the set from closing the <script> element it is embedded in
+    let data = data.to_string().replace("</", "<\\/");
+
+    QUIZ_TEMPLATE
+        .replace("{{style}}", STYLE)
+        .replace("{{set}}", &data)
+}
+
+pub fn run(args: ExportArgs) -> Result<(), AskmeError> {
+    let set = AskmeSet::from_files(&args.filenames)?;
+    if set.questions.is_empty() {
+        return Err(AskmeError::new("no questions provided!", None));
+    }
+
+    let format = match (args.markdown, args.text, args.latex) {
+        (true, _, _) => Format::Markdown,
+        (_, true, _) => Format::Text,
+        (_, _, true) => Format::Latex,
+        _ => return write_output(&set, args.output.as_deref(), &export_html(&set, &args)),
+    };
+
+    let options = WorksheetOptions {
+        shuffle: args.shuffle.or(set.settings.shuffle).unwrap_or_default(),
+        multichoice: args.multichoice,
+        max_choices: args
+            .max_choices
+            .map(|n| n as usize)
+            .or(set.settings.max_choices)
+            .unwrap_or(4),
+        distractors: args
+            .distractors
+            .or(set.settings.distractors)
+            .unwrap_or_default(),
+    };
+    let seed = args.seed.unwrap_or_else(|| rng().gen());
+
+    let path = match &args.output {
+        Some(path) => path,
+        None => {
+            let worksheet = Worksheet::new(&set, &options, seed, None);
+            return write_output(&set, None, &worksheet.render(format, args.key));
+        }
+    };
+
+    for n in 1..=args.variants {
+        let (version, path) = match args.variants {
+            1 => (None, path.clone()),
+            _ => (Some(n as usize), variant_path(path, &n.to_string())),
+        };
+
+        let worksheet = Worksheet::new(&set, &options, seed.wrapping_add(n - 1), version);
+        write_output(&set, Some(&path), &worksheet.render(format, false))?;
+        write_output(
+            &set,
+            Some(&variant_path(&path, "key")),
+            &worksheet.render(format, true),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Insert `suffix` before the extension of `path`: `quiz.md` becomes `quiz-key.md`.
+fn variant_path(path: &str, suffix: &str) -> String {
+    let path = Path::new(path);
+    let name = match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(ext)) => format!(
+            "{}-{}.{}",
+            stem.to_string_lossy(),
+            suffix,
+            ext.to_string_lossy()
+        ),
+        (Some(stem), None) => format!("{}-{}", stem.to_string_lossy(), suffix),
+        _ => suffix.to_string(),
+    };
+
+    path.with_file_name(name).to_string_lossy().to_string()
+}
+
+fn write_output(set: &AskmeSet, path: Option<&str>, content: &str) -> Result<(), AskmeError> {
+    match path {
+        Some(path) => match fs::write(path, content) {
+            Ok(()) => {
+                print_info(&format!("exported '{}' to {}", set.title, path));
+                Ok(())
+            }
+            Err(e) => Err(AskmeError::new(
+                format!("Failed to write '{}': {}", path, e),
+                Some(Box::from(e)),
+            )),
+        },
+        None => {
+            print!("{}", content);
+            Ok(())
+        }
+    }
+}
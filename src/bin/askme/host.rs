@@ -0,0 +1,522 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use askme::config::Options;
+use askme::generate::{choices_available, multiple_choices, Distractors};
+use askme::grading::{
+    check_answer, grade_list, grade_order, parse_option, parse_order, split_list_answer,
+    OrderScoring,
+};
+use askme::prelude::*;
+use askme::ui::{self, Span, Tone};
+use askme::{print_info, print_warning, shuffle_arr};
+use clap::Args;
+
+use crate::multiplayer::{send, ClientMessage, HostMessage, Standing, DEFAULT_PORT};
+
+const MAX_POINTS: f64 = 1000.0; // Points for a correct answer given right away
+
+#[derive(Args, Debug)]
+pub struct HostArgs {
+    #[arg(required = true, help = "Set files to be quizzed on")]
+    filenames: Vec<String>,
+
+    #[arg(long, short = 'p', default_value_t = DEFAULT_PORT, help = "Port to listen on")]
+    port: u16,
+
+    #[arg(
+        long,
+        default_value = "0.0.0.0",
+        help = "Address to listen on (127.0.0.1 to only allow this computer)"
+    )]
+    bind: String,
+
+    #[arg(
+        long,
+        short = 's',
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        help = "Enable shuffling [default: the set's, or false]"
+    )]
+    shuffle: Option<bool>,
+
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        help = "Make answers to the questions case-sensitive [default: the set's, or false]"
+    )]
+    case_sensitive: Option<bool>,
+
+    #[arg(long, help = "Give lettered choices for single-answer questions")]
+    multichoice: bool,
+
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u64).range(2..=26),
+        help = "Maximum choices for each multiple choice question [default: the set's, or 4]"
+    )]
+    max_choices: Option<u64>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "How the wrong choices are picked [default: the set's, or random]"
+    )]
+    distractors: Option<Distractors>,
+
+    #[arg(
+        long,
+        short = 't',
+        default_value_t = 20.0,
+        help = "Seconds the players get to answer each question"
+    )]
+    question_time: f64,
+
+    #[arg(
+        long,
+        short = 'd',
+        default_value_t = 3.0,
+        help = "Delay between questions (seconds)"
+    )]
+    duration: f64,
+}
+
+/// What the connection threads tell the game.
+enum Event {
+    Joined(usize, String, TcpStream), // Connection id, requested name and stream to reply on
+    Answered(usize, usize, String, Instant), // Connection id, question number, answer and when it came
+    Left(usize),
+    Start, // The host pressed enter
+}
+
+struct Player {
+    id: usize,
+    name: String,
+    stream: TcpStream,
+    points: u64,
+}
+
+/// How a question was put to the players.
+struct Asked {
+    choices: Vec<String>, // Choices, or shuffled items of an ordering question
+    correct: usize,       // Index of the correct choice
+    shown: Vec<usize>,    // Correct position of each shuffled item
+}
+
+fn read_messages(id: usize, stream: TcpStream, events: Sender<Event>) {
+    let reader = match stream.try_clone() {
+        Ok(reader) => BufReader::new(reader),
+        Err(_) => return,
+    };
+
+    for line in reader.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let event = match serde_json::from_str::<ClientMessage>(&line) {
+            Ok(ClientMessage::Join { name }) => match stream.try_clone() {
+                Ok(stream) => Event::Joined(id, name, stream),
+                Err(_) => break,
+            },
+            Ok(ClientMessage::Answer { question, answer }) => {
+                Event::Answered(id, question, answer, Instant::now())
+            }
+            Err(e) => {
+                let message = format!("invalid message: {}", e);
+                let _ = send(&stream, &HostMessage::Error { message });
+                continue;
+            }
+        };
+
+        if events.send(event).is_err() {
+            return;
+        }
+    }
+
+    let _ = events.send(Event::Left(id));
+}
+
+fn accept_players(listener: TcpListener, events: Sender<Event>) {
+    for (id, stream) in listener.incoming().enumerate() {
+        if let Ok(stream) = stream {
+            let events = events.clone();
+            thread::spawn(move || read_messages(id, stream, events));
+        }
+    }
+}
+
+fn wait_for_start(events: Sender<Event>) {
+    let mut line = String::new();
+    while std::io::stdin().read_line(&mut line).is_ok_and(|n| n > 0) {
+        if events.send(Event::Start).is_err() {
+            return;
+        }
+        line.clear();
+    }
+}
+
+/// Points for an answer: the score, scaled from the full points for an
+/// instant answer down to half of them at the end of the time.
+fn points(score: f64, elapsed: Duration, time_limit: f64) -> u64 {
+    let speed = 1.0 - (elapsed.as_secs_f64() / time_limit).min(1.0);
+    (score * MAX_POINTS * (0.5 + 0.5 * speed)).round() as u64
+}
+
+struct Game {
+    set: AskmeSet,
+    args: HostArgs,
+    players: Vec<Player>,
+    events: Receiver<Event>,
+}
+
+impl Game {
+    fn standings(&self) -> Vec<Standing> {
+        let mut standings = self
+            .players
+            .iter()
+            .map(|p| Standing {
+                name: p.name.clone(),
+                points: p.points,
+            })
+            .collect::<Vec<Standing>>();
+        standings.sort_by_key(|s| std::cmp::Reverse(s.points));
+        standings
+    }
+
+    fn broadcast(&self, message: &HostMessage) {
+        for player in &self.players {
+            let _ = send(&player.stream, message);
+        }
+    }
+
+    fn add_player(&mut self, id: usize, name: String, stream: TcpStream) {
+        if self.players.iter().any(|p| p.id == id) {
+            let message = "already joined".to_string();
+            let _ = send(&stream, &HostMessage::Error { message });
+            return;
+        }
+
+        let base = match name.trim() {
+            "" => "Player".to_string(),
+            name => name.to_string(),
+        };
+        let mut name = base.clone();
+        let mut n = 1;
+        while self.players.iter().any(|p| p.name == name) {
+            n += 1;
+            name = format!("{} ({})", base, n);
+        }
+
+        let welcome = HostMessage::Welcome {
+            name: name.clone(),
+            title: self.set.title.clone(),
+            subtitle: self.set.subtitle.clone(),
+            questions: self.set.questions.len(),
+        };
+        if send(&stream, &welcome).is_ok() {
+            print_info(&format!("{} joined", name));
+            self.players.push(Player {
+                id,
+                name,
+                stream,
+                points: 0,
+            });
+        }
+    }
+
+    fn remove_player(&mut self, id: usize) {
+        if let Some(idx) = self.players.iter().position(|p| p.id == id) {
+            print_warning(&format!("{} left", self.players.remove(idx).name));
+        }
+    }
+
+    fn wait_in_lobby(&mut self) {
+        loop {
+            match self.events.recv() {
+                Ok(Event::Joined(id, name, stream)) => self.add_player(id, name, stream),
+                Ok(Event::Left(id)) => self.remove_player(id),
+                Ok(Event::Start) if self.players.is_empty() => {
+                    print_warning("nobody has joined yet")
+                }
+                Ok(Event::Start) | Err(_) => return,
+                Ok(Event::Answered(..)) => (),
+            }
+        }
+    }
+
+    fn ask(&self, question: &Question) -> Asked {
+        match question.kind {
+            QuestionKind::Single if self.args.multichoice => {
+                // Without enough answers to go around, every question gets them all
+                let available = choices_available(question, &self.set.questions);
+                let max_choices = self
+                    .args
+                    .max_choices
+                    .map(|n| n as usize)
+                    .or(self.set.settings.max_choices)
+                    .unwrap_or(4);
+                let len = max_choices.min(available);
+                let distractors = self
+                    .args
+                    .distractors
+                    .or(self.set.settings.distractors)
+                    .unwrap_or_default();
+                let (choices, correct) =
+                    multiple_choices(question, &self.set.questions, len, distractors);
+                Asked {
+                    choices,
+                    correct,
+                    shown: Vec::new(),
+                }
+            }
+            QuestionKind::Order => {
+                let shown = shuffle_arr(&(0..question.answers.len()).collect::<Vec<usize>>());
+                Asked {
+                    choices: shown.iter().map(|&p| question.answers[p].clone()).collect(),
+                    correct: 0,
+                    shown,
+                }
+            }
+            _ => Asked {
+                choices: Vec::new(),
+                correct: 0,
+                shown: Vec::new(),
+            },
+        }
+    }
+
+    fn grade(&self, question: &Question, asked: &Asked, answer: &str) -> f64 {
+        // The set's settings apply unless overridden, as in `askme export`
+        let case_sensitive = self
+            .args
+            .case_sensitive
+            .or(self.set.settings.case_sensitive)
+            .unwrap_or_default();
+
+        match question.kind {
+            QuestionKind::Single if !asked.choices.is_empty() => {
+                match parse_option(answer, &asked.choices) == Some(asked.correct) {
+                    true => 1.0,
+                    false => 0.0,
+                }
+            }
+            QuestionKind::Single => match check_answer(question, answer, case_sensitive) {
+                true => 1.0,
+                false => 0.0,
+            },
+            QuestionKind::List => {
+                grade_list(question, &split_list_answer(answer), case_sensitive).score
+            }
+            QuestionKind::Order => match parse_order(answer, asked.shown.len()) {
+                Some(order) => {
+                    let given = order
+                        .iter()
+                        .map(|&i| asked.shown[i])
+                        .collect::<Vec<usize>>();
+                    grade_order(&given, OrderScoring::Position)
+                }
+                None => 0.0,
+            },
+        }
+    }
+
+    /// Collect the players' answers to question `number` until everyone has
+    /// answered or the time is up.
+    fn collect_answers(
+        &mut self,
+        number: usize,
+        asked_at: Instant,
+    ) -> HashMap<usize, (String, Duration)> {
+        let deadline = asked_at + Duration::from_secs_f64(self.args.question_time);
+        let mut answers = HashMap::new();
+
+        while answers.len() < self.players.len() {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match self.events.recv_timeout(timeout) {
+                Ok(Event::Answered(id, question, answer, at)) => {
+                    if question == number && self.players.iter().any(|p| p.id == id) {
+                        answers.entry(id).or_insert((answer, at - asked_at));
+                    }
+                }
+                Ok(Event::Joined(_, _, stream)) => {
+                    let message = "the game has already started".to_string();
+                    let _ = send(&stream, &HostMessage::Error { message });
+                }
+                Ok(Event::Left(id)) => self.remove_player(id),
+                Ok(Event::Start) => (),
+                Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        answers
+    }
+
+    fn print_leaderboard(&self) {
+        for (rank, standing) in self.standings().iter().enumerate() {
+            ui::print_spans(&[
+                Span::plain(format!(" {}. {} ", rank + 1, standing.name)),
+                Span::new(Tone::Highlight, format!("{}", standing.points)),
+            ]);
+        }
+        ui::print(Tone::Plain, "");
+    }
+
+    fn play(&mut self) {
+        let shuffle = self
+            .args
+            .shuffle
+            .or(self.set.settings.shuffle)
+            .unwrap_or_default();
+        let questions = match shuffle {
+            true => shuffle_arr(&self.set.questions),
+            false => self.set.questions.clone(),
+        };
+        let total = questions.len();
+
+        for (idx, question) in questions.iter().enumerate() {
+            if self.players.is_empty() {
+                print_warning("everybody left, ending the game");
+                break;
+            }
+
+            let number = idx + 1;
+            let asked = self.ask(question);
+
+            ui::print(Tone::Highlight, &format!("Question {}/{}", number, total));
+            ui::show_question(&question.title);
+            self.broadcast(&HostMessage::Question {
+                number,
+                total,
+                title: question.title.clone(),
+                kind: question.kind,
+                choices: asked.choices.clone(),
+                time_limit: self.args.question_time,
+            });
+
+            let answers = self.collect_answers(number, Instant::now());
+
+            let correct_answers = match asked.choices.is_empty() || !asked.shown.is_empty() {
+                true => question.answers.clone(),
+                false => vec![asked.choices[asked.correct].clone()],
+            };
+            let graded = self
+                .players
+                .iter()
+                .map(|player| match answers.get(&player.id) {
+                    Some((answer, elapsed)) => {
+                        let score = self.grade(question, &asked, answer);
+                        (score, points(score, *elapsed, self.args.question_time))
+                    }
+                    None => (0.0, 0),
+                })
+                .collect::<Vec<(f64, u64)>>();
+
+            for (player, (score, earned)) in self.players.iter_mut().zip(graded) {
+                player.points += earned;
+
+                let _ = send(
+                    &player.stream,
+                    &HostMessage::Result {
+                        number,
+                        score,
+                        points: earned,
+                        answers: correct_answers.clone(),
+                    },
+                );
+            }
+
+            ui::print_labelled(" Answer: ", &correct_answers.join(", "));
+            ui::print(Tone::Plain, "");
+
+            let is_final = number == total;
+            if is_final {
+                ui::print(Tone::Bold, "Final leaderboard");
+            }
+            self.print_leaderboard();
+
+            self.broadcast(&HostMessage::Leaderboard {
+                players: self.standings(),
+                is_final,
+            });
+            if !is_final {
+                ui::pause(self.args.duration);
+            }
+        }
+    }
+}
+
+pub fn run(args: HostArgs) -> Result<(), AskmeError> {
+    // The times are checked like the modes' settings, as clap takes any number
+    Options {
+        question_time: Some(args.question_time),
+        wait_duration: Some(args.duration),
+        ..Options::default()
+    }
+    .validate()?;
+
+    let set = AskmeSet::from_files(&args.filenames)?;
+    if set.questions.is_empty() {
+        return Err(AskmeError::new("no questions provided!", None));
+    }
+
+    let listener = match TcpListener::bind((args.bind.as_str(), args.port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            return Err(AskmeError::new(
+                format!("Failed to listen on {}:{}: {}", args.bind, args.port, e),
+                Some(Box::from(e)),
+            ))
+        }
+    };
+
+    let (sender, events) = mpsc::channel();
+    let accept_sender = sender.clone();
+    thread::spawn(move || accept_players(listener, accept_sender));
+    thread::spawn(move || wait_for_start(sender));
+
+    ui::show_title(&set.title, &set.subtitle);
+    print_info(&format!(
+        "hosting on port {}: players can run `askme join <this computer's address>:{}`",
+        args.port, args.port
+    ));
+    print_info("press enter to start the game once everyone has joined");
+
+    let mut game = Game {
+        set,
+        args,
+        players: Vec::new(),
+        events,
+    };
+    game.wait_in_lobby();
+    game.play();
+
+    Ok(())
+}
@@ -0,0 +1,210 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::{BufRead, BufReader};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use askme::prelude::*;
+use askme::ui::{self, Span, Tone};
+use askme::{format_score, print_info};
+use clap::Args;
+
+use crate::multiplayer::{send, ClientMessage, HostMessage, Standing, DEFAULT_PORT};
+
+#[derive(Args, Debug)]
+pub struct JoinArgs {
+    #[arg(help = "Address of the host, e.g. 192.168.1.20 or 192.168.1.20:7878")]
+    address: String,
+
+    #[arg(long, short = 'n', help = "Name shown on the leaderboard")]
+    name: Option<String>,
+}
+
+fn network_error(e: std::io::Error) -> AskmeError {
+    AskmeError::new(
+        format!("Lost the connection to the host: {}", e),
+        Some(Box::from(e)),
+    )
+}
+
+fn print_leaderboard(players: &[Standing], me: &str) {
+    for (rank, standing) in players.iter().enumerate() {
+        let tone = match standing.name == me {
+            true => Tone::Bold,
+            false => Tone::Plain,
+        };
+        ui::print_spans(&[
+            Span::new(tone, format!(" {}. {} ", rank + 1, standing.name)),
+            Span::new(Tone::Highlight, format!("{}", standing.points)),
+        ]);
+    }
+    ui::print(Tone::Plain, "");
+}
+
+/// When the time to answer a question sent now runs out, or `None` if the
+/// host sent a time limit that can't be waited for (e.g. negative or NaN).
+fn deadline(time_limit: f64) -> Option<Instant> {
+    Duration::try_from_secs_f64(time_limit)
+        .ok()
+        .filter(|limit| !limit.is_zero())
+        .and_then(|limit| Instant::now().checked_add(limit))
+}
+
+/// Ask a question sent by the host, returning the answer if one was given in time.
+fn answer_question(
+    title: &str,
+    kind: QuestionKind,
+    choices: &[String],
+    deadline: Instant,
+) -> Option<String> {
+    ui::show_question(title);
+    ui::print_options(choices);
+
+    let message = match (kind, choices.is_empty()) {
+        (QuestionKind::Order, _) => "Order (letters):",
+        (_, false) => "Choice (letter):",
+        (QuestionKind::List, _) => "Answer (comma-separated):",
+        _ => "Answer:",
+    };
+    ui::read_line(message, Some(deadline))
+}
+
+pub fn run(args: JoinArgs) -> Result<(), AskmeError> {
+    let address = match args.address.contains(':') {
+        true => args.address.clone(),
+        false => format!("{}:{}", args.address, DEFAULT_PORT),
+    };
+
+    let name = match args.name {
+        Some(name) => name,
+        None => ui::read_line("Your name:", None).unwrap_or_default(),
+    };
+
+    let stream = match TcpStream::connect(&address) {
+        Ok(stream) => stream,
+        Err(e) => {
+            return Err(AskmeError::new(
+                format!("Failed to connect to {}: {}", address, e),
+                Some(Box::from(e)),
+            ))
+        }
+    };
+    let reader = BufReader::new(stream.try_clone().map_err(network_error)?);
+
+    send(&stream, &ClientMessage::Join { name }).map_err(network_error)?;
+
+    let mut me = String::new();
+    let mut total_score = 0.0;
+
+    for line in reader.lines() {
+        let line = line.map_err(network_error)?;
+
+        let message = match serde_json::from_str::<HostMessage>(&line) {
+            Ok(message) => message,
+            Err(e) => {
+                return Err(AskmeError::new(
+                    format!("Invalid message from the host: {}", e),
+                    Some(Box::from(e)),
+                ))
+            }
+        };
+
+        match message {
+            HostMessage::Welcome {
+                name,
+                title,
+                subtitle,
+                questions,
+            } => {
+                ui::show_title(&title, &subtitle);
+                print_info(&format!(
+                    "joined as {} ({} questions), waiting for the host to start",
+                    name, questions
+                ));
+                me = name;
+            }
+            HostMessage::Question {
+                number,
+                total,
+                title,
+                kind,
+                choices,
+                time_limit,
+            } => {
+                let deadline = match (number, deadline(time_limit)) {
+                    (1.., Some(deadline)) => deadline,
+                    _ => {
+                        return Err(AskmeError::new(
+                            format!(
+                            "Invalid message from the host: question {} with {} seconds to answer",
+                            number, time_limit
+                        ),
+                            None,
+                        ))
+                    }
+                };
+
+                ui::show_progress(number - 1, total);
+                ui::print(Tone::Highlight, &format!("Question {}/{}", number, total));
+
+                if let Some(answer) = answer_question(&title, kind, &choices, deadline) {
+                    let answer = ClientMessage::Answer {
+                        question: number,
+                        answer,
+                    };
+                    send(&stream, &answer).map_err(network_error)?;
+                    ui::print(Tone::Plain, "Waiting for the others..");
+                }
+            }
+            HostMessage::Result {
+                score,
+                points,
+                answers,
+                ..
+            } => {
+                total_score += score;
+                match score {
+                    s if s >= 1.0 => ui::print(Tone::Correct, "✔️ That's correct!"),
+                    s if s > 0.0 => ui::print(Tone::Partial, "➖ Partially correct"),
+                    _ => ui::print(Tone::Incorrect, "❌ Not quite correct.."),
+                }
+                if score < 1.0 {
+                    ui::print_labelled("Answer: ", &answers.join(", "));
+                }
+                ui::print(Tone::Highlight, &format!("+{} points", points));
+                ui::print(Tone::Plain, "");
+            }
+            HostMessage::Leaderboard { players, is_final } => {
+                if is_final {
+                    ui::print(Tone::Bold, "Final leaderboard");
+                }
+                print_leaderboard(&players, &me);
+
+                if is_final {
+                    ui::print(
+                        Tone::Highlight,
+                        &format!("Correct answers: {}", format_score(total_score)),
+                    );
+                    return Ok(());
+                }
+            }
+            HostMessage::Error { message } => {
+                return Err(AskmeError::new(format!("The host said: {}", message), None))
+            }
+        }
+    }
+
+    Err(AskmeError::new("The host ended the game", None))
+}
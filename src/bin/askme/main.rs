@@ -0,0 +1,61 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod export;
+mod host;
+mod join;
+mod multiplayer;
+mod serve;
+mod stats;
+mod worksheet;
+
+use clap::{Parser, Subcommand};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Show statistics about past sessions
+    Stats(stats::StatsArgs),
+    /// Export a set to share it with people who don't have AskMe
+    Export(export::ExportArgs),
+    /// Host a multiplayer quiz on the local network
+    Host(host::HostArgs),
+    /// Join a multiplayer quiz hosted with `askme host`
+    Join(join::JoinArgs),
+    /// Take a set as a quiz in the web browser
+    Serve(serve::ServeArgs),
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let result = match args.command {
+        Command::Stats(args) => stats::run(args),
+        Command::Export(args) => export::run(args),
+        Command::Host(args) => host::run(args),
+        Command::Join(args) => join::run(args),
+        Command::Serve(args) => serve::run(args),
+    };
+
+    if let Err(e) = result {
+        askme::print_err(&format!("{}", e));
+        std::process::exit(1)
+    }
+}
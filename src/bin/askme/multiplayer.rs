@@ -0,0 +1,97 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Protocol spoken between `askme host` and `askme join`.
+///
+/// Every message is a JSON object on a line of its own, with its kind in the
+/// `type` field. A player connects and sends `join`; the host replies with
+/// `welcome` (or `error`, e.g. once the game has started). Once the host
+/// starts the game, every question is sent to all players as `question`.
+/// Players reply with `answer`, naming the question number, and are sent
+/// their `result` once everyone has answered or the time is up, followed by
+/// the `leaderboard`. The last leaderboard has `final` set, after which the
+/// host closes the connection.
+///
+/// Answers are graded like in the terminal modes: list answers are
+/// comma-separated, ordering answers are the letters of the `choices` in
+/// order and multiple choice answers are the choice's label (a letter, or a
+/// number past 26 choices) or the choice itself.
+use std::io::{self, Write};
+use std::net::TcpStream;
+
+use askme::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// Messages sent by players to the host.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Join {
+        name: String, // Name shown on the leaderboard
+    },
+    Answer {
+        question: usize, // Number of the question answered
+        answer: String,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Standing {
+    pub name: String,
+    pub points: u64,
+}
+
+/// Messages sent by the host to players.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HostMessage {
+    Welcome {
+        name: String, // Name the player got (made unique if it was taken)
+        title: String,
+        subtitle: String,
+        questions: usize, // Number of questions in the game
+    },
+    Question {
+        number: usize, // From 1 to `total`
+        total: usize,
+        title: String,
+        kind: QuestionKind,
+        #[serde(default)]
+        choices: Vec<String>, // Choices to pick from or items to put in order (may be empty)
+        time_limit: f64, // Seconds to answer in
+    },
+    Result {
+        number: usize,
+        score: f64,           // From 0.0 to 1.0
+        points: u64,          // Points earned, more for faster answers
+        answers: Vec<String>, // The correct answer(s)
+    },
+    Leaderboard {
+        players: Vec<Standing>, // Highest points first
+        #[serde(rename = "final")]
+        is_final: bool, // Whether the game is over
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// Send `message` as one line of JSON.
+pub fn send<M: Serialize>(mut stream: &TcpStream, message: &M) -> io::Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())
+}
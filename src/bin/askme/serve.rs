@@ -0,0 +1,682 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+use askme::generate::Distractors;
+use askme::grading::{option_labels, SelectScoring};
+use askme::history::record_session;
+use askme::prelude::*;
+use askme::random::rng;
+use askme::session::{Answer, Feedback, PromptKind, Session, SessionMode, SessionSettings};
+use askme::{format_score, print_info, print_warning};
+use clap::Args;
+use rand::Rng;
+
+const MAX_BODY_LEN: usize = 64 * 1024;
+const SESSION_COOKIE: &str = "askme_session";
+const MAX_QUIZZES: usize = 100; // Quizzes kept at once, before the oldest make way
+
+pub const STYLE: &str = "
+body { font-family: sans-serif; max-width: 40em; margin: 2em auto; padding: 0 1em; color: #222; }
+h1 { color: #0a7f8f; margin-bottom: 0; }
+.subtitle { color: #2a4f9f; margin-top: 0.2em; }
+.question { font-size: 1.4em; font-weight: bold; }
+.correct { color: #1a7f1a; } .partial { color: #a07000; } .incorrect { color: #b02020; }
+button, input[type=text] { font-size: 1em; padding: 0.4em 0.8em; margin: 0.2em 0; }
+.choices button { display: block; width: 100%; text-align: left; }
+table { border-collapse: collapse; } td, th { padding: 0.3em 0.8em; border-bottom: 1px solid #ddd; text-align: left; }
+";
+
+#[derive(Args, Debug)]
+pub struct ServeArgs {
+    #[arg(required = true, help = "Set files to be quizzed on")]
+    filenames: Vec<String>,
+
+    #[arg(
+        long,
+        short = 'p',
+        default_value_t = 8080,
+        help = "Port to listen on (localhost only)"
+    )]
+    port: u16,
+
+    #[arg(
+        long,
+        short = 's',
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        help = "Enable shuffling [default: the set's, or false]"
+    )]
+    shuffle: Option<bool>,
+
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        help = "Make answers to the questions case-sensitive [default: the set's, or false]"
+    )]
+    case_sensitive: Option<bool>,
+
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u64).range(2..),
+        help = "Maximum choices for each multiple choice question [default: the set's, or 4]"
+    )]
+    max_choices: Option<u64>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "How the wrong choices are picked [default: the set's, or random]"
+    )]
+    distractors: Option<Distractors>,
+}
+
+const MODES: [SessionMode; 3] = [
+    SessionMode::Memorize,
+    SessionMode::Multichoice,
+    SessionMode::Correctme,
+];
+
+fn mode_label(mode: SessionMode) -> &'static str {
+    match mode {
+        SessionMode::Memorize => "Type the answers",
+        SessionMode::Multichoice => "Multiple choice",
+        SessionMode::Correctme => "True or false",
+    }
+}
+
+/// A quiz taken in one browser.
+struct Quiz {
+    session: Session,
+    feedback: Option<(String, Feedback)>, // Last question and its feedback, until the user moves on
+    recorded: bool,                       // Whether the finished quiz was saved to the history
+    started: Instant,                     // When the quiz was started
+}
+
+struct Request {
+    method: String,
+    path: String,
+    cookies: HashMap<String, String>,
+    form: HashMap<String, String>,
+}
+
+struct Response {
+    status: &'static str,
+    headers: Vec<String>,
+    body: String,
+}
+
+impl Response {
+    fn page(body: String) -> Self {
+        Response {
+            status: "200 OK",
+            headers: vec!["Content-Type: text/html; charset=utf-8".to_string()],
+            body,
+        }
+    }
+
+    fn redirect(location: &str) -> Self {
+        Response {
+            status: "303 See Other",
+            headers: vec![format!("Location: {}", location)],
+            body: String::new(),
+        }
+    }
+
+    fn status(status: &'static str) -> Self {
+        Response {
+            status,
+            headers: vec!["Content-Type: text/plain; charset=utf-8".to_string()],
+            body: status.to_string(),
+        }
+    }
+}
+
+struct Server {
+    set: AskmeSet,
+    args: ServeArgs,
+    quizzes: HashMap<String, Quiz>,
+}
+
+fn escape(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '&' => "&amp;".to_string(),
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            '"' => "&quot;".to_string(),
+            '\'' => "&#39;".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// Decode an `application/x-www-form-urlencoded` value.
+fn url_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                match std::str::from_utf8(&bytes[i + 1..i + 3])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+fn parse_form(body: &str) -> HashMap<String, String> {
+    body.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (url_decode(key), url_decode(value)))
+        .collect()
+}
+
+fn read_request(stream: &TcpStream) -> Option<Request> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).ok()?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let target = parts.next()?;
+    let path = target.split('?').next().unwrap_or("/").to_string();
+
+    let mut content_length = 0;
+    let mut cookies = HashMap::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).ok()? == 0 || line.trim().is_empty() {
+            break;
+        }
+
+        let (name, value) = match line.split_once(':') {
+            Some((name, value)) => (name.trim().to_lowercase(), value.trim()),
+            None => continue,
+        };
+        match name.as_str() {
+            "content-length" => content_length = value.parse().ok()?,
+            "cookie" => cookies.extend(
+                value
+                    .split(';')
+                    .filter_map(|cookie| cookie.trim().split_once('='))
+                    .map(|(name, value)| (name.to_string(), value.to_string())),
+            ),
+            _ => (),
+        }
+    }
+
+    if content_length > MAX_BODY_LEN {
+        return None;
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).ok()?;
+
+    Some(Request {
+        method,
+        path,
+        cookies,
+        form: parse_form(&String::from_utf8_lossy(&body)),
+    })
+}
+
+fn write_response(mut stream: &TcpStream, response: Response) {
+    let mut head = format!("HTTP/1.1 {}\r\n", response.status);
+    for header in &response.headers {
+        head.push_str(&format!("{}\r\n", header));
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        response.body.len()
+    ));
+
+    let _ = stream
+        .write_all(head.as_bytes())
+        .and_then(|_| stream.write_all(response.body.as_bytes()));
+}
+
+fn lettered(items: &[String]) -> String {
+    option_labels(items.len())
+        .iter()
+        .zip(items)
+        .map(|(label, item)| format!("<li>{}) {}</li>", label, escape(item)))
+        .collect()
+}
+
+fn verdict(score: f64) -> &'static str {
+    match score {
+        s if s >= 1.0 => "<p class=\"correct\">✔️ That's correct!</p>",
+        s if s > 0.0 => "<p class=\"partial\">➖ Partially correct</p>",
+        _ => "<p class=\"incorrect\">❌ Not quite correct..</p>",
+    }
+}
+
+impl Server {
+    fn page(&self, content: &str) -> Response {
+        Response::page(format!(
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\">\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+             <title>{title}</title><style>{style}</style></head>\n<body>\
+             <h1>{title}</h1><p class=\"subtitle\">{subtitle}</p>\n{content}\n</body></html>\n",
+            title = escape(&self.set.title),
+            subtitle = escape(&self.set.subtitle),
+            style = STYLE,
+            content = content,
+        ))
+    }
+
+    fn handle(&mut self, request: Request) -> Response {
+        let id = request.cookies.get(SESSION_COOKIE).cloned();
+
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/") => self.index(),
+            ("POST", "/start") => self.start(id, &request.form),
+            ("GET", "/quiz") => match id.filter(|id| self.quizzes.contains_key(id)) {
+                Some(id) => self.show_quiz(&id),
+                None => Response::redirect("/"),
+            },
+            ("POST", "/answer") => {
+                if let Some(quiz) = id.and_then(|id| self.quizzes.get_mut(&id)) {
+                    answer(quiz, &request.form);
+                }
+                Response::redirect("/quiz")
+            }
+            ("POST", "/next") => {
+                if let Some(quiz) = id.and_then(|id| self.quizzes.get_mut(&id)) {
+                    quiz.feedback = None;
+                }
+                Response::redirect("/quiz")
+            }
+            ("GET", _) => Response::status("404 Not Found"),
+            _ => Response::status("405 Method Not Allowed"),
+        }
+    }
+
+    fn index(&self) -> Response {
+        let buttons = MODES
+            .iter()
+            .map(|&mode| {
+                format!(
+                    "<button name=\"mode\" value=\"{}\">{}</button> ",
+                    mode.name(),
+                    mode_label(mode)
+                )
+            })
+            .collect::<String>();
+
+        self.page(&format!(
+            "<p>{} questions. How would you like to be quizzed?</p>\
+             <form method=\"post\" action=\"/start\">{}</form>",
+            self.set.questions.len(),
+            buttons
+        ))
+    }
+
+    /// Forget the quiz started longest ago once there are `MAX_QUIZZES`,
+    /// going for ones already saved to the history first.
+    fn make_room(&mut self) {
+        if self.quizzes.len() < MAX_QUIZZES {
+            return;
+        }
+
+        let oldest = self
+            .quizzes
+            .iter()
+            .min_by_key(|(_, quiz)| (!quiz.recorded, quiz.started))
+            .map(|(id, _)| id.clone());
+        if let Some(id) = oldest {
+            self.quizzes.remove(&id);
+        }
+    }
+
+    /// Start a quiz in the browser that had quiz `id`, which makes way for it.
+    fn start(&mut self, id: Option<String>, form: &HashMap<String, String>) -> Response {
+        let mode = match MODES
+            .into_iter()
+            .find(|mode| form.get("mode").map(String::as_str) == Some(mode.name()))
+        {
+            Some(mode) => mode,
+            None => return Response::status("400 Bad Request"),
+        };
+
+        // The set's settings apply unless overridden, as in `askme export`
+        let set_settings = &self.set.settings;
+        let settings = SessionSettings {
+            shuffle: self
+                .args
+                .shuffle
+                .or(set_settings.shuffle)
+                .unwrap_or_default(),
+            case_sensitive: self
+                .args
+                .case_sensitive
+                .or(set_settings.case_sensitive)
+                .unwrap_or_default(),
+            max_choices: self
+                .args
+                .max_choices
+                .map(|n| n as usize)
+                .or(set_settings.max_choices)
+                .unwrap_or(4),
+            distractors: self
+                .args
+                .distractors
+                .or(set_settings.distractors)
+                .unwrap_or_default(),
+            select_all: set_settings.select_all.unwrap_or_default(),
+            select_scoring: set_settings
+                .select_scoring
+                .unwrap_or(SelectScoring::Partial),
+            ..SessionSettings::default()
+        };
+
+        if let Some(id) = id {
+            self.quizzes.remove(&id);
+        }
+        self.make_room();
+
+        let id = format!("{:016x}", rng().gen::<u64>());
+        self.quizzes.insert(
+            id.clone(),
+            Quiz {
+                session: Session::new(&self.set, mode, settings),
+                feedback: None,
+                recorded: false,
+                started: Instant::now(),
+            },
+        );
+
+        let mut response = Response::redirect("/quiz");
+        response.headers.push(format!(
+            "Set-Cookie: {}={}; Path=/; HttpOnly; SameSite=Strict",
+            SESSION_COOKIE, id
+        ));
+        response
+    }
+
+    fn show_quiz(&mut self, id: &str) -> Response {
+        let quiz = match self.quizzes.get_mut(id) {
+            Some(quiz) => quiz,
+            None => return Response::redirect("/"),
+        };
+
+        let content = if let Some((title, feedback)) = &quiz.feedback {
+            show_feedback(title, feedback)
+        } else if !quiz.session.is_finished() {
+            ask(&mut quiz.session)
+        } else {
+            if !quiz.recorded {
+                let mode = quiz.session.mode().name();
+                record_session(&self.set, mode, quiz.session.results());
+                quiz.recorded = true;
+            }
+            summary(&quiz.session)
+        };
+
+        self.page(&content)
+    }
+
+    fn run(&mut self) -> Result<(), AskmeError> {
+        let listener = match TcpListener::bind((Ipv4Addr::LOCALHOST, self.args.port)) {
+            Ok(listener) => listener,
+            Err(e) => {
+                return Err(AskmeError::new(
+                    format!("Failed to listen on port {}: {}", self.args.port, e),
+                    Some(Box::from(e)),
+                ))
+            }
+        };
+
+        print_info(&format!(
+            "serving '{}' on http://{}:{}/ (press Ctrl+C to stop)",
+            self.set.title,
+            Ipv4Addr::LOCALHOST,
+            self.args.port
+        ));
+
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    print_warning(&format!("failed to accept a connection: {}", e));
+                    continue;
+                }
+            };
+
+            // Don't let a stalled browser hold up everyone else
+            let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+
+            let response = match read_request(&stream) {
+                Some(request) => self.handle(request),
+                None => Response::status("400 Bad Request"),
+            };
+            write_response(&stream, response);
+        }
+
+        Ok(())
+    }
+}
+
+/// Put the current question to the user, returning the question page.
+fn ask(session: &mut Session) -> String {
+    // Reloading the page keeps the same prompt until it is answered
+    let prompt = match session.prompt() {
+        Some(prompt) => prompt,
+        None => return String::new(),
+    };
+
+    let progress = format!("<p>Question {}/{}</p>", prompt.number, prompt.total);
+    let title = escape(&prompt.question);
+    let text_input = |placeholder: &str| {
+        format!(
+            "<input type=\"text\" name=\"answer\" autocomplete=\"off\" autofocus required \
+             placeholder=\"{}\"> <button>Answer</button>",
+            placeholder
+        )
+    };
+
+    let form = match &prompt.kind {
+        PromptKind::Text => format!("<p class=\"question\">{}</p>{}", title, text_input("")),
+        PromptKind::List { .. } => format!(
+            "<p class=\"question\">{}</p>{}",
+            title,
+            text_input("Separate the items with commas")
+        ),
+        PromptKind::Order { items } => format!(
+            "<p class=\"question\">{}</p><ul>{}</ul>{}",
+            title,
+            lettered(items),
+            text_input("Letters in order, e.g. bca")
+        ),
+        PromptKind::Choice { choices } => {
+            let buttons = option_labels(choices.len())
+                .iter()
+                .zip(choices)
+                .enumerate()
+                .map(|(i, (label, choice))| {
+                    format!(
+                        "<button name=\"choice\" value=\"{}\">{}) {}</button>",
+                        i,
+                        label,
+                        escape(choice)
+                    )
+                })
+                .collect::<String>();
+            format!(
+                "<p class=\"question\">{}</p><div class=\"choices\">{}</div>",
+                title, buttons
+            )
+        }
+        // Each box has a name of its own, as the form keeps one value per name
+        PromptKind::Selection { choices } => {
+            let boxes = option_labels(choices.len())
+                .iter()
+                .zip(choices)
+                .enumerate()
+                .map(|(i, (label, choice))| {
+                    format!(
+                        "<label><input type=\"checkbox\" name=\"pick{}\"> {}) {}</label><br>",
+                        i,
+                        label,
+                        escape(choice)
+                    )
+                })
+                .collect::<String>();
+            format!(
+                "<p class=\"question\">{}</p><p>Pick all that apply</p>{}<button>Submit</button>",
+                title, boxes
+            )
+        }
+        PromptKind::Statement { answer } => format!(
+            "<p>Is this correct?</p><p class=\"question\">\"{} is {}\"</p>\
+             <button name=\"answer\" value=\"yes\">Yes</button> \
+             <button name=\"answer\" value=\"no\">No</button>",
+            title,
+            escape(answer)
+        ),
+    };
+
+    format!(
+        "{}<form method=\"post\" action=\"/answer\">{}</form>",
+        progress, form
+    )
+}
+
+/// Grade the answer to the current question. Answers that don't fit the
+/// question leave it open, so it is simply asked again.
+fn answer(quiz: &mut Quiz, form: &HashMap<String, String>) {
+    if quiz.feedback.is_some() {
+        return;
+    }
+    let prompt = match quiz.session.prompt() {
+        Some(prompt) => prompt,
+        None => return,
+    };
+
+    let answer = match (&prompt.kind, form.get("choice"), form.get("answer")) {
+        (PromptKind::Choice { .. }, Some(choice), _) => choice.parse().ok().map(Answer::Choice),
+        (PromptKind::Selection { choices }, _, None) => {
+            let picked = (0..choices.len())
+                .filter(|i| form.contains_key(&format!("pick{}", i)))
+                .collect::<Vec<usize>>();
+            (!picked.is_empty()).then_some(Answer::Choices(picked))
+        }
+        (_, _, Some(input)) => Answer::parse(&prompt.kind, input),
+        _ => None,
+    };
+
+    if let Some(feedback) = answer.and_then(|answer| quiz.session.answer(answer).ok()) {
+        quiz.feedback = Some((prompt.question, feedback));
+    }
+}
+
+fn show_feedback(title: &str, feedback: &Feedback) -> String {
+    let mut lines = Vec::new();
+    if !feedback.missed.is_empty() {
+        lines.push(format!(
+            "<b>Missed:</b> {}",
+            escape(&feedback.missed.join(", "))
+        ));
+    }
+    if !feedback.extra.is_empty() {
+        lines.push(format!(
+            "<b>Not part of the answer:</b> {}",
+            escape(&feedback.extra.join(", "))
+        ));
+    }
+    if !feedback.is_correct() {
+        lines.push(format!(
+            "<b>{}</b> → {}",
+            escape(title),
+            escape(&feedback.correct.join(", "))
+        ));
+    }
+
+    format!(
+        "{}{}<form method=\"post\" action=\"/next\">\
+         <button autofocus>Next</button></form>",
+        verdict(feedback.score),
+        lines
+            .iter()
+            .map(|line| format!("<p>{}</p>", line))
+            .collect::<String>()
+    )
+}
+
+fn summary(session: &Session) -> String {
+    let rows = session
+        .results()
+        .iter()
+        .map(|result| {
+            let mark = match result.score {
+                s if s >= 1.0 => "<span class=\"correct\">✔️</span>",
+                s if s > 0.0 => "<span class=\"partial\">➖</span>",
+                _ => "<span class=\"incorrect\">❌</span>",
+            };
+            format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(&result.title),
+                escape(result.answer.as_deref().unwrap_or("-")),
+                mark
+            )
+        })
+        .collect::<String>();
+
+    format!(
+        "<p class=\"question\">Correct answers: {}/{}</p>\
+         <table><tr><th>Question</th><th>Your answer</th><th></th></tr>{}</table>\
+         <p><a href=\"/\">Start again</a></p>",
+        format_score(session.score()),
+        session.total(),
+        rows
+    )
+}
+
+pub fn run(args: ServeArgs) -> Result<(), AskmeError> {
+    let set = AskmeSet::from_files(&args.filenames)?;
+    if set.questions.is_empty() {
+        return Err(AskmeError::new("no questions provided!", None));
+    }
+
+    let mut server = Server {
+        set,
+        args,
+        quizzes: HashMap::new(),
+    };
+    server.run()
+}
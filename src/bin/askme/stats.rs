@@ -0,0 +1,442 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use askme::history::{
+    canonical_set_file, format_date, history_path, load_sessions, question_stats, AnswerStats,
+    SessionRecord,
+};
+use askme::prelude::*;
+use clap::Args;
+use colored::Colorize;
+use serde::Serialize;
+
+const SECS_PER_DAY: u64 = 86400;
+
+#[derive(Args, Debug)]
+pub struct StatsArgs {
+    #[arg(long, help = "Only include sessions of this set file")]
+    set: Option<String>,
+
+    #[arg(long, help = "Only include sessions in this mode (e.g. memorize)")]
+    mode: Option<String>,
+
+    #[arg(
+        long,
+        default_value_t = 10,
+        help = "How many of the hardest questions to show"
+    )]
+    top: usize,
+
+    #[arg(long, help = "Print the statistics as JSON")]
+    json: bool,
+}
+
+#[derive(Serialize)]
+struct DayStats {
+    date: String,
+    sessions: usize,
+    answers: usize,
+    accuracy: f64,
+}
+
+#[derive(Serialize)]
+struct QuestionStats {
+    set_title: String,
+    title: String,
+    attempts: usize,
+    accuracy: f64,
+    average_response_time: f64,
+}
+
+#[derive(Serialize)]
+struct GroupStats {
+    name: String,
+    answers: usize,
+    accuracy: f64,
+    average_response_time: f64,
+}
+
+#[derive(Serialize)]
+struct Stats {
+    sessions: usize,
+    answers: usize,
+    accuracy: f64,
+    average_response_time: f64,
+    current_streak: usize, // Consecutive days studied, up to today
+    longest_streak: usize, // Most consecutive days studied
+    days: Vec<DayStats>,
+    hardest: Vec<QuestionStats>,
+    tags: Vec<GroupStats>,
+    modes: Vec<GroupStats>,
+}
+
+fn group_stats(groups: BTreeMap<String, AnswerStats>) -> Vec<GroupStats> {
+    groups
+        .into_iter()
+        .map(|(name, stats)| GroupStats {
+            name,
+            answers: stats.attempts,
+            accuracy: stats.accuracy(),
+            average_response_time: stats.average_time(),
+        })
+        .collect()
+}
+
+/// Current and longest runs of consecutive days with at least one session.
+fn day_streaks(sessions: &[SessionRecord], today: u64) -> (usize, usize) {
+    let days = sessions
+        .iter()
+        .map(|s| s.timestamp / SECS_PER_DAY)
+        .collect::<BTreeSet<u64>>();
+
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous = None;
+    for &day in &days {
+        run = match previous {
+            Some(prev) if prev + 1 == day => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(day);
+    }
+
+    // A streak is still going if the last session was today or yesterday
+    let current = match previous {
+        Some(last) if last + 1 >= today => run,
+        _ => 0,
+    };
+
+    (current, longest)
+}
+
+fn compute_stats(sessions: &[SessionRecord], top: usize, today: u64) -> Stats {
+    let mut overall = AnswerStats::default();
+    let mut days = BTreeMap::<u64, (usize, AnswerStats)>::new();
+    let mut tags = BTreeMap::<String, AnswerStats>::new();
+    let mut modes = BTreeMap::<String, AnswerStats>::new();
+
+    for session in sessions {
+        let day = days.entry(session.timestamp / SECS_PER_DAY).or_default();
+        day.0 += 1;
+
+        for record in &session.questions {
+            overall.add(record);
+            day.1.add(record);
+            modes.entry(session.mode.clone()).or_default().add(record);
+            for tag in &record.tags {
+                tags.entry(tag.clone()).or_default().add(record);
+            }
+        }
+    }
+
+    let set_titles = sessions
+        .iter()
+        .map(|s| (s.set_file.clone(), s.set_title.clone()))
+        .collect::<BTreeMap<String, String>>();
+
+    let mut hardest = question_stats(sessions)
+        .into_iter()
+        .map(|((set_file, title), stats)| QuestionStats {
+            set_title: set_titles.get(&set_file).cloned().unwrap_or_default(),
+            title,
+            attempts: stats.attempts,
+            accuracy: stats.accuracy(),
+            average_response_time: stats.average_time(),
+        })
+        .collect::<Vec<QuestionStats>>();
+    hardest.sort_by(|a, b| {
+        a.accuracy
+            .total_cmp(&b.accuracy)
+            .then(b.attempts.cmp(&a.attempts))
+    });
+    hardest.truncate(top);
+
+    let (current_streak, longest_streak) = day_streaks(sessions, today);
+
+    Stats {
+        sessions: sessions.len(),
+        answers: overall.attempts,
+        accuracy: overall.accuracy(),
+        average_response_time: overall.average_time(),
+        current_streak,
+        longest_streak,
+        days: days
+            .into_iter()
+            .map(|(day, (sessions, stats))| DayStats {
+                date: format_date(day * SECS_PER_DAY),
+                sessions,
+                answers: stats.attempts,
+                accuracy: stats.accuracy(),
+            })
+            .collect(),
+        hardest,
+        tags: group_stats(tags),
+        modes: group_stats(modes),
+    }
+}
+
+fn percent(accuracy: f64) -> String {
+    format!("{:.1}%", accuracy * 100.0)
+}
+
+fn print_groups(heading: &str, groups: &[GroupStats]) {
+    if groups.is_empty() {
+        return;
+    }
+
+    println!("{}", heading.bold());
+    for group in groups {
+        println!(
+            " {}: {} of {} answers, {:.1}s on average",
+            group.name,
+            percent(group.accuracy),
+            group.answers,
+            group.average_response_time
+        );
+    }
+    println!();
+}
+
+fn print_stats(stats: &Stats) {
+    println!("{}", "Overall:".bold());
+    println!(" Sessions: {}", stats.sessions);
+    println!(
+        " Accuracy: {} of {} answers",
+        percent(stats.accuracy),
+        stats.answers
+    );
+    println!(
+        " Average response time: {:.1}s",
+        stats.average_response_time
+    );
+    println!(
+        " Streak: {} days (longest: {} days)\n",
+        stats.current_streak, stats.longest_streak
+    );
+
+    println!("{}", "Accuracy over time:".bold());
+    for day in &stats.days {
+        println!(
+            " {}: {} ({} sessions, {} answers)",
+            day.date,
+            percent(day.accuracy),
+            day.sessions,
+            day.answers
+        );
+    }
+    println!();
+
+    println!("{}", "Hardest questions:".bold());
+    for question in &stats.hardest {
+        println!(
+            " {} ({}): {} of {} attempts, {:.1}s on average",
+            question.title,
+            question.set_title,
+            percent(question.accuracy),
+            question.attempts,
+            question.average_response_time
+        );
+    }
+    println!();
+
+    print_groups("Accuracy per tag:", &stats.tags);
+    print_groups("Accuracy per mode:", &stats.modes);
+}
+
+pub fn run(args: StatsArgs) -> Result<(), AskmeError> {
+    let set_file = args.set.as_deref().map(canonical_set_file);
+    let sessions = load_sessions(&history_path()?)?
+        .into_iter()
+        .filter(|s| set_file.iter().all(|f| s.set_file == *f))
+        .filter(|s| args.mode.iter().all(|m| s.mode == *m))
+        .collect::<Vec<SessionRecord>>();
+
+    let today = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / SECS_PER_DAY)
+        .unwrap_or(0);
+    let stats = compute_stats(&sessions, args.top, today);
+
+    if args.json {
+        match serde_json::to_string_pretty(&stats) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                return Err(AskmeError::new(
+                    format!("Failed to serialize statistics: {}", e),
+                    Some(Box::from(e)),
+                ))
+            }
+        }
+    } else if sessions.is_empty() {
+        askme::print_info("no sessions recorded yet");
+    } else {
+        print_stats(&stats);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use askme::history::QuestionRecord;
+
+    const DAY: u64 = 20000; // Some day, counted from the Unix epoch
+
+    fn record(title: &str, tags: &[&str], score: f64) -> QuestionRecord {
+        QuestionRecord {
+            title: title.to_string(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            score,
+            response_time: 2.0,
+            timed_out: false,
+        }
+    }
+
+    fn session(timestamp: u64, mode: &str, questions: Vec<QuestionRecord>) -> SessionRecord {
+        SessionRecord {
+            timestamp,
+            set_file: "/sets/elements.yaml".to_string(),
+            set_title: "Elements".to_string(),
+            mode: mode.to_string(),
+            questions,
+        }
+    }
+
+    /// Sessions at the given seconds after the start of `DAY`.
+    fn sessions_at(seconds: &[u64]) -> Vec<SessionRecord> {
+        seconds
+            .iter()
+            .map(|&secs| session(DAY * SECS_PER_DAY + secs, "memorize", vec![]))
+            .collect()
+    }
+
+    #[test]
+    fn no_history_has_no_streak() {
+        assert_eq!(day_streaks(&[], DAY), (0, 0));
+    }
+
+    #[test]
+    fn sessions_on_the_same_day_count_once() {
+        let sessions = sessions_at(&[60, 3600, 80000]);
+        assert_eq!(day_streaks(&sessions, DAY), (1, 1));
+    }
+
+    #[test]
+    fn streak_continues_across_midnight() {
+        // A minute before and a minute after midnight
+        let sessions = sessions_at(&[SECS_PER_DAY - 60, SECS_PER_DAY + 60]);
+        assert_eq!(day_streaks(&sessions, DAY + 1), (2, 2));
+    }
+
+    #[test]
+    fn gap_breaks_the_streak() {
+        let day = SECS_PER_DAY;
+        let sessions = sessions_at(&[0, day, day * 2, day * 4, day * 5]);
+        assert_eq!(day_streaks(&sessions, DAY + 5), (2, 3));
+    }
+
+    #[test]
+    fn streak_lasts_until_the_end_of_the_next_day() {
+        let day = SECS_PER_DAY;
+        let sessions = sessions_at(&[0, day]);
+        assert_eq!(day_streaks(&sessions, DAY + 2), (2, 2));
+        assert_eq!(day_streaks(&sessions, DAY + 3), (0, 2));
+    }
+
+    #[test]
+    fn empty_history_has_empty_stats() {
+        let stats = compute_stats(&[], 10, DAY);
+
+        assert_eq!(stats.sessions, 0);
+        assert_eq!(stats.answers, 0);
+        assert_eq!(stats.accuracy, 0.0);
+        assert!(stats.days.is_empty() && stats.hardest.is_empty());
+        assert!(stats.tags.is_empty() && stats.modes.is_empty());
+    }
+
+    #[test]
+    fn stats_are_grouped_by_day_tag_and_mode() {
+        let start = DAY * SECS_PER_DAY;
+        let sessions = vec![
+            session(
+                start,
+                "memorize",
+                vec![record("H", &["gas"], 1.0), record("Fe", &["metal"], 0.0)],
+            ),
+            session(start + 60, "multichoice", vec![record("H", &["gas"], 1.0)]),
+            session(
+                start + SECS_PER_DAY,
+                "memorize",
+                vec![record("Fe", &["metal"], 0.5)],
+            ),
+        ];
+
+        let stats = compute_stats(&sessions, 10, DAY + 1);
+
+        assert_eq!(stats.sessions, 3);
+        assert_eq!(stats.answers, 4);
+        assert_eq!(stats.accuracy, 0.625);
+        assert_eq!((stats.current_streak, stats.longest_streak), (2, 2));
+
+        let days = stats
+            .days
+            .iter()
+            .map(|day| (day.sessions, day.answers, day.accuracy))
+            .collect::<Vec<_>>();
+        assert_eq!(days, [(2, 3, 2.0 / 3.0), (1, 1, 0.5)]);
+
+        let tags = stats
+            .tags
+            .iter()
+            .map(|tag| (tag.name.as_str(), tag.answers, tag.accuracy))
+            .collect::<Vec<_>>();
+        assert_eq!(tags, [("gas", 2, 1.0), ("metal", 2, 0.25)]);
+
+        let modes = stats
+            .modes
+            .iter()
+            .map(|mode| (mode.name.as_str(), mode.answers))
+            .collect::<Vec<_>>();
+        assert_eq!(modes, [("memorize", 3), ("multichoice", 1)]);
+    }
+
+    #[test]
+    fn hardest_questions_come_first() {
+        let sessions = vec![session(
+            DAY * SECS_PER_DAY,
+            "memorize",
+            vec![
+                record("H", &[], 1.0),
+                record("Fe", &[], 0.0),
+                record("Fe", &[], 1.0),
+                record("Au", &[], 0.0),
+            ],
+        )];
+
+        let stats = compute_stats(&sessions, 2, DAY);
+
+        let hardest = stats
+            .hardest
+            .iter()
+            .map(|question| (question.title.as_str(), question.attempts))
+            .collect::<Vec<_>>();
+        assert_eq!(hardest, [("Au", 1), ("Fe", 2)]);
+        assert_eq!(stats.hardest[0].set_title, "Elements");
+    }
+}
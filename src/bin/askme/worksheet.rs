@@ -0,0 +1,287 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use askme::generate::{choices_available, multiple_choices, Distractors};
+use askme::grading::option_labels;
+use askme::prelude::*;
+use askme::random::seed_rng;
+use askme::shuffle_arr;
+
+const BLANK: &str = "______________________________";
+
+/// Formats a worksheet can be written in.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Format {
+    Markdown,
+    Text,
+    Latex,
+}
+
+/// What the student gets to fill in.
+enum Body {
+    Blanks(usize),        // Lines to write the answer(s) on
+    Choices(Vec<String>), // Lettered options to pick from
+    Order(Vec<String>),   // Lettered items to put in order
+}
+
+struct Item {
+    title: String,
+    body: Body,
+    answer: String, // Answer shown in the answer key
+}
+
+/// One version of a worksheet.
+pub struct Worksheet {
+    title: String,
+    subtitle: String,
+    version: Option<usize>, // Version number, if there are several
+    items: Vec<Item>,
+}
+
+pub struct WorksheetOptions {
+    pub shuffle: bool,            // Whether to shuffle the questions
+    pub multichoice: bool,        // Whether to give choices for single-answer questions
+    pub max_choices: usize,       // Choices for each multiple choice question
+    pub distractors: Distractors, // How the wrong choices are picked
+}
+
+impl Worksheet {
+    /// Generate a worksheet from `set`. The same `seed` always gives the
+    /// same worksheet.
+    pub fn new(
+        set: &AskmeSet,
+        options: &WorksheetOptions,
+        seed: u64,
+        version: Option<usize>,
+    ) -> Self {
+        seed_rng(seed);
+
+        let questions = match options.shuffle {
+            true => shuffle_arr(&set.questions),
+            false => set.questions.clone(),
+        };
+
+        let items = questions
+            .iter()
+            .map(|qn| match qn.kind {
+                QuestionKind::Single if options.multichoice => {
+                    // Without enough answers to go around, every question gets them all
+                    let available = choices_available(qn, &set.questions);
+                    let max_choices = options.max_choices.min(available);
+                    let (choices, correct) =
+                        multiple_choices(qn, &set.questions, max_choices, options.distractors);
+                    let labels = option_labels(choices.len());
+                    Item {
+                        title: qn.title.clone(),
+                        answer: format!("{}) {}", labels[correct], choices[correct]),
+                        body: Body::Choices(choices),
+                    }
+                }
+                QuestionKind::Single => Item {
+                    title: qn.title.clone(),
+                    body: Body::Blanks(1),
+                    answer: qn.answers.join(" / "),
+                },
+                QuestionKind::List => Item {
+                    title: qn.title.clone(),
+                    body: Body::Blanks(qn.answers.len()),
+                    answer: qn.answers.join(", "),
+                },
+                QuestionKind::Order => {
+                    let shown = shuffle_arr(&(0..qn.answers.len()).collect::<Vec<usize>>());
+                    let labels = option_labels(shown.len());
+                    let order = (0..shown.len())
+                        .map(|pos| labels[shown.iter().position(|&p| p == pos).unwrap()].as_str())
+                        .collect::<Vec<&str>>()
+                        .join(" ");
+                    Item {
+                        title: qn.title.clone(),
+                        body: Body::Order(shown.iter().map(|&p| qn.answers[p].clone()).collect()),
+                        answer: format!("{} ({})", order, qn.answers.join(", ")),
+                    }
+                }
+            })
+            .collect();
+
+        Worksheet {
+            title: set.title.clone(),
+            subtitle: set.subtitle.clone(),
+            version,
+            items,
+        }
+    }
+
+    fn heading(&self, answer_key: bool) -> String {
+        let mut heading = self.title.clone();
+        if let Some(version) = self.version {
+            heading.push_str(&format!(" (version {})", version));
+        }
+        if answer_key {
+            heading.push_str(" - answer key");
+        }
+        heading
+    }
+
+    /// Render the worksheet, or its answer key, in `format`.
+    pub fn render(&self, format: Format, answer_key: bool) -> String {
+        match format {
+            Format::Markdown => self.render_markdown(answer_key),
+            Format::Text => self.render_text(answer_key),
+            Format::Latex => self.render_latex(answer_key),
+        }
+    }
+
+    fn render_markdown(&self, answer_key: bool) -> String {
+        let mut out = format!(
+            "# {}\n\n{}\n\n",
+            escape_markdown(&self.heading(answer_key)),
+            escape_markdown(&self.subtitle)
+        );
+        if !answer_key {
+            out.push_str(&format!("Name: {}\n\n", BLANK.replace('_', "\\_")));
+        }
+
+        for (i, item) in self.items.iter().enumerate() {
+            out.push_str(&format!(
+                "{}. **{}**\n\n",
+                i + 1,
+                escape_markdown(&item.title)
+            ));
+
+            let lines = match (&item.body, answer_key) {
+                (_, true) => vec![escape_markdown(&item.answer)],
+                (Body::Blanks(count), false) => vec![BLANK.replace('_', "\\_"); *count],
+                (Body::Choices(items), false) | (Body::Order(items), false) => {
+                    option_labels(items.len())
+                        .iter()
+                        .zip(items)
+                        .map(|(label, option)| format!("{}) {}", label, escape_markdown(option)))
+                        .collect()
+                }
+            };
+            // Two trailing spaces keep the lines apart
+            out.push_str(&format!("   {}\n\n", lines.join("  \n   ")));
+
+            if let (Body::Order(_), false) = (&item.body, answer_key) {
+                out.push_str(&format!("   Order: {}\n\n", BLANK.replace('_', "\\_")));
+            }
+        }
+
+        out
+    }
+
+    fn render_text(&self, answer_key: bool) -> String {
+        let heading = self.heading(answer_key);
+        let mut out = format!(
+            "{}\n{}\n{}\n\n",
+            heading,
+            "=".repeat(heading.chars().count()),
+            self.subtitle
+        );
+        if !answer_key {
+            out.push_str(&format!("Name: {}\n\n", BLANK));
+        }
+
+        for (i, item) in self.items.iter().enumerate() {
+            out.push_str(&format!("{}. {}\n", i + 1, item.title));
+
+            match (&item.body, answer_key) {
+                (_, true) => out.push_str(&format!("   {}\n", item.answer)),
+                (Body::Blanks(count), false) => {
+                    for _ in 0..*count {
+                        out.push_str(&format!("\n   {}\n", BLANK));
+                    }
+                }
+                (Body::Choices(items), false) | (Body::Order(items), false) => {
+                    for (label, option) in option_labels(items.len()).iter().zip(items) {
+                        out.push_str(&format!("   {}) {}\n", label, option));
+                    }
+                    if let Body::Order(_) = item.body {
+                        out.push_str(&format!("\n   Order: {}\n", BLANK));
+                    }
+                }
+            }
+            out.push('\n');
+        }
+
+        out
+    }
+
+    fn render_latex(&self, answer_key: bool) -> String {
+        let mut out = format!(
+            "\\documentclass{{article}}\n\
+             \\usepackage[T1]{{fontenc}}\n\
+             \\usepackage[utf8]{{inputenc}}\n\
+             \\begin{{document}}\n\n\
+             \\section*{{{}}}\n{}\n\n",
+            escape_latex(&self.heading(answer_key)),
+            escape_latex(&self.subtitle)
+        );
+        if !answer_key {
+            out.push_str("\\noindent Name: \\rule{6cm}{0.4pt}\n\n");
+        }
+
+        out.push_str("\\begin{enumerate}\n");
+        for item in &self.items {
+            out.push_str(&format!(
+                "\\item \\textbf{{{}}}\n",
+                escape_latex(&item.title)
+            ));
+
+            match (&item.body, answer_key) {
+                (_, true) => out.push_str(&format!("\n{}\n", escape_latex(&item.answer))),
+                (Body::Blanks(count), false) => {
+                    for _ in 0..*count {
+                        out.push_str("\n\\vspace{0.6cm}\\noindent\\rule{8cm}{0.4pt}\n");
+                    }
+                }
+                (Body::Choices(items), false) | (Body::Order(items), false) => {
+                    out.push_str("\\begin{enumerate}\n");
+                    for (label, option) in option_labels(items.len()).iter().zip(items) {
+                        out.push_str(&format!("  \\item[{})] {}\n", label, escape_latex(option)));
+                    }
+                    out.push_str("\\end{enumerate}\n");
+                    if let Body::Order(_) = item.body {
+                        out.push_str("Order: \\rule{4cm}{0.4pt}\n");
+                    }
+                }
+            }
+            out.push('\n');
+        }
+        out.push_str("\\end{enumerate}\n\n\\end{document}\n");
+
+        out
+    }
+}
+
+fn escape_markdown(text: &str) -> String {
+    text.chars()
+        .flat_map(|c| match c {
+            '\\' | '*' | '_' | '`' | '#' | '[' | ']' | '<' | '>' => vec!['\\', c],
+            c => vec![c],
+        })
+        .collect()
+}
+
+fn escape_latex(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\\' => "\\textbackslash{}".to_string(),
+            '~' => "\\textasciitilde{}".to_string(),
+            '^' => "\\textasciicircum{}".to_string(),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => format!("\\{}", c),
+            c => c.to_string(),
+        })
+        .collect()
+}
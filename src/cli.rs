@@ -0,0 +1,239 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Command-line arguments and start-up shared by the mode binaries: loading
+/// the sets, resolving the settings, drawing the questions to ask and, once
+/// the mode has run, printing the score and recording the session.
+use std::fmt::Display;
+
+use clap::Args;
+
+use crate::config::{Config, Options};
+use crate::history::{keep_weak_questions, record_session};
+use crate::prelude::*;
+use crate::random::seed_rng;
+use crate::sampling::{parse_tag_counts, sample_questions, TagCounts};
+use crate::scoring::{parse_grades, GradeScale, Summary};
+use crate::ui::{self, set_ui, UiKind};
+use crate::{print_correct_answers, print_err, print_info, print_warning};
+
+/// Arguments every mode takes, flattened into its own.
+#[derive(Args, Debug)]
+#[command(about = None, long_about = None)]
+pub struct CommonArgs {
+    #[arg(required = true, help = "Set files to be quizzed on")]
+    pub filenames: Vec<String>,
+
+    #[arg(
+        long = "duration",
+        short = 'd',
+        help = "Choose how long the delay is between new questions (seconds, can be decimal) [default: 1]"
+    )]
+    pub wait_duration: Option<f64>,
+
+    #[arg(
+        long,
+        short = 'L',
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        help = "Choose if you would like to be quizzed in a loop"
+    )]
+    pub loop_questions: Option<bool>,
+
+    #[arg(
+        long,
+        value_name = "FRACTION",
+        help = "Share of the credit lost for each :hint taken [default: 0]"
+    )]
+    pub hint_penalty: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "GRADE=PERCENT,...",
+        value_parser = parse_grades,
+        help = "Grades and the percentage of the points each needs [default: A=90,B=80,C=70,D=60,F=0]"
+    )]
+    pub grades: Option<GradeScale>,
+
+    #[arg(
+        long,
+        short = 'M',
+        help = "Only ask questions answered wrong in recent sessions"
+    )]
+    pub mistakes: bool,
+
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = 5,
+        requires = "mistakes",
+        help = "How many recent sessions of each set to look for mistakes in"
+    )]
+    pub mistakes_sessions: usize,
+
+    #[arg(
+        long,
+        value_name = "PERCENT",
+        requires = "mistakes",
+        help = "Also ask questions whose accuracy over the whole history is below PERCENT"
+    )]
+    pub below: Option<f64>,
+
+    #[arg(
+        long,
+        value_name = "TAG=N,...",
+        value_parser = parse_tag_counts,
+        help = "Only ask N questions of each TAG, drawn at random (topped up to --count)"
+    )]
+    pub sample_by_tag: Option<TagCounts>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Frontend to use: line-by-line prompts or a full-screen terminal UI [default: line]"
+    )]
+    pub ui: Option<UiKind>,
+
+    #[arg(
+        long,
+        conflicts_with = "ui",
+        help = "Print questions and read answers as JSON lines, for other programs to drive"
+    )]
+    pub machine: bool,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Config file to read settings from [default: askme/config.yaml in the config directory]"
+    )]
+    pub config: Option<String>,
+
+    #[arg(long, help = "Profile from the config file to apply")]
+    pub profile: Option<String>,
+
+    #[arg(
+        long,
+        help = "Seed for shuffling and drawing questions, to get the same session again"
+    )]
+    pub seed: Option<u64>,
+}
+
+impl CommonArgs {
+    /// The settings given with these arguments.
+    pub fn options(&self) -> Options {
+        Options {
+            wait_duration: self.wait_duration,
+            loop_questions: self.loop_questions,
+            hint_penalty: self.hint_penalty,
+            grades: self.grades.clone(),
+            ui: match self.machine {
+                true => Some(UiKind::Machine),
+                false => self.ui,
+            },
+            ..Options::default()
+        }
+    }
+}
+
+/// A mode binary, as far as starting it goes.
+pub struct Mode {
+    pub name: &'static str, // Name of the mode, as used in the config and the history
+    pub single_only: bool,  // Whether list and ordering questions are left out
+    pub adaptive: bool,     // Whether the mode can pick questions adaptively
+}
+
+fn exit_with(e: impl Display) -> ! {
+    print_err(&format!("{}", e));
+    std::process::exit(1)
+}
+
+/// Run mode `A` on the sets given in `args`, with the settings given on the
+/// command line (`cli`) over the config's, then print the score and record
+/// the session. Exits if anything fails.
+pub fn run<A, S>(mode: &Mode, args: &CommonArgs, cli: &Options)
+where
+    A: AskmeMode<S, Summary>,
+    S: From<Options>,
+{
+    // Report errors as JSON too
+    if args.machine {
+        let _ = set_ui(UiKind::Machine);
+    }
+
+    if let Some(seed) = args.seed {
+        seed_rng(seed);
+    }
+
+    let mut set = AskmeSet::from_files(&args.filenames).unwrap_or_else(|e| exit_with(e));
+
+    // List and ordering questions are only asked in memorize mode
+    if mode.single_only {
+        let left_out = set.keep_single_questions();
+        if set.questions.is_empty() {
+            exit_with(format!(
+                "no questions can be asked in {} mode: list and ordering questions need askme-memorize",
+                mode.name
+            ));
+        } else if left_out > 0 {
+            print_warning(&format!(
+                "skipping {} list or ordering question(s), which need askme-memorize",
+                left_out
+            ));
+        }
+    }
+
+    let options = Config::load(args.config.as_deref())
+        .and_then(|config| config.resolve(mode.name, args.profile.as_deref(), &set.options(), cli))
+        .unwrap_or_else(|e| exit_with(e));
+
+    if args.mistakes {
+        let below = args.below.map(|percent| percent / 100.0);
+        if let Err(e) = keep_weak_questions(&mut set, args.mistakes_sessions, below) {
+            exit_with(e);
+        }
+
+        if set.questions.is_empty() {
+            print_info("no mistakes to review!");
+            std::process::exit(0)
+        }
+    }
+
+    // Adaptive mode draws its own questions, --count at a time
+    let count = match mode.adaptive && options.adaptive.unwrap_or_default() {
+        true => None,
+        false => options.count,
+    };
+    // Only the questions asked are drawn; the rest of the set still provides
+    // wrong options (see `AskmeSet::all_questions`)
+    if count.is_some() || args.sample_by_tag.is_some() {
+        set.questions = sample_questions(&set.questions, count, args.sample_by_tag.as_ref());
+    }
+
+    if let Err(e) = set_ui(options.ui.unwrap_or_default()) {
+        exit_with(e);
+    }
+
+    let grades = options.grades.clone().unwrap_or_default();
+    let mut app = A::new(set, S::from(options));
+
+    let result = app.run();
+    ui::finish();
+
+    let summary = result.unwrap_or_else(|e| exit_with(e));
+    print_correct_answers(&summary, &grades);
+
+    record_session(app.get_set(), mode.name, app.get_results());
+}
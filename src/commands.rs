@@ -0,0 +1,339 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Commands typed at an answer prompt instead of an answer, like `:skip`.
+///
+/// The input functions of the crate recognize them. `:hint`, `:score`,
+/// `:mark` and `:help` are handled on the spot and the prompt is shown
+/// again; `:skip`, `:reveal`, `:quit` and `:undo` end the prompt without an
+/// answer, and the mode asks `take_interrupt` what happened.
+use std::cell::RefCell;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use crate::history::data_dir;
+use crate::prelude::*;
+use crate::scoring::Summary;
+use crate::ui::{self, Span, Tone};
+use crate::{format_score, print_info, print_warning};
+
+const MARKED_FILE_NAME: &str = "marked.jsonl";
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Command {
+    Skip,   // Move on without answering
+    Hint,   // Show a bit more of the answer
+    Reveal, // Show the answer and move on
+    Score,  // Show the score so far
+    Quit,   // End the session
+    Mark,   // Flag the question for editing later
+    Undo,   // Stop counting the last answer
+    Help,   // List the commands
+}
+
+static COMMANDS: [(&str, Command, &str); 8] = [
+    ("skip", Command::Skip, "move on without answering"),
+    ("hint", Command::Hint, "show a bit more of the answer"),
+    ("reveal", Command::Reveal, "show the answer and move on"),
+    ("score", Command::Score, "show the score so far"),
+    ("quit", Command::Quit, "end the session"),
+    ("mark", Command::Mark, "flag the question for editing later"),
+    ("undo", Command::Undo, "don't count the last answer"),
+    ("help", Command::Help, "list the commands"),
+];
+
+impl Command {
+    /// Read a command, typed as `:name`. Returns `None` for anything that
+    /// doesn't start with a colon.
+    pub fn parse(input: &str) -> Option<Result<Command, String>> {
+        let name = input.trim().strip_prefix(':')?.trim().to_lowercase();
+
+        match COMMANDS.iter().find(|(n, _, _)| *n == name) {
+            Some((_, command, _)) => Some(Ok(*command)),
+            None => Some(Err(name)),
+        }
+    }
+}
+
+/// What happened to typed input.
+pub(crate) enum Handled {
+    Answer,    // It is an answer
+    Again,     // It was a command that was dealt with; prompt again
+    Interrupt, // It was a command that ends the prompt
+}
+
+/// What the commands know about the question being asked.
+#[derive(Default)]
+struct Context {
+    questions: Vec<Question>, // Questions being asked (several in match rounds)
+    hints: usize,             // Hints given so far
+    so_far: Summary,          // Totals before these questions
+    interrupt: Option<Command>,
+    quit: bool,
+    undone: usize, // Answers (or match rounds) taken back since `take_undone`
+}
+
+thread_local! {
+    static CONTEXT: RefCell<Context> = RefCell::new(Context::default());
+}
+
+/// Tell the commands about the questions about to be asked, along with the
+/// results so far. Until this is called, only `:skip`, `:quit` and `:help`
+/// are available, e.g. in exam mode.
+pub fn begin_question(questions: &[Question], results: &[QuestionResult]) {
+    CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        context.questions = questions.to_vec();
+        context.hints = 0;
+        context.interrupt = None;
+        context.so_far = Summary::from_results(results);
+    });
+}
+
+/// Number of hints taken for the questions being asked.
+pub fn hints_taken() -> usize {
+    CONTEXT.with(|context| context.borrow().hints)
+}
+
+/// The command that ended the last prompt without an answer, if any. `None`
+/// means the time ran out (or the question was skipped with an empty answer).
+pub fn take_interrupt() -> Option<Command> {
+    CONTEXT.with(|context| context.borrow_mut().interrupt.take())
+}
+
+/// The command that ended a prompt without `answer`, if any (see
+/// `take_interrupt`). A prompt that was answered leaves none.
+pub fn interrupt_of<T>(answer: &Option<T>) -> Option<Command> {
+    match answer {
+        Some(_) => None,
+        None => take_interrupt(),
+    }
+}
+
+/// End the session as `:quit` does, e.g. when the user presses Ctrl+C. The
+/// prompt being shown ends without an answer.
+pub(crate) fn quit() {
+    CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        context.interrupt = Some(Command::Quit);
+        context.quit = true;
+    });
+}
+
+/// Stop counting the last `count` of `results` after `:undo`, returning
+/// them. The frontend forgets them too, and so do the mastery queue and the
+/// adaptive selector, which ask `take_undone` after each question.
+pub fn undo(results: &mut Vec<QuestionResult>, count: usize) -> Vec<QuestionResult> {
+    let undone = results.split_off(results.len().saturating_sub(count));
+    ui::undo_results(undone.len());
+    CONTEXT.with(|context| context.borrow_mut().undone += 1);
+    undone
+}
+
+/// Take back the last of `results` after `:undo` (see `undo`), letting the
+/// user know which answer no longer counts.
+pub fn undo_last(results: &mut Vec<QuestionResult>) -> Option<QuestionResult> {
+    let result = undo(results, 1).pop()?;
+    print_info(&format!(
+        "the answer to \"{}\" no longer counts",
+        result.title
+    ));
+    Some(result)
+}
+
+/// How many times an answer (or a match round) was taken back with `:undo`
+/// since the last call.
+pub fn take_undone() -> usize {
+    CONTEXT.with(|context| std::mem::take(&mut context.borrow_mut().undone))
+}
+
+/// Whether the user asked to end the session.
+pub fn has_quit() -> bool {
+    CONTEXT.with(|context| context.borrow().quit)
+}
+
+/// The first `shown` letters of `answer`, with the others blanked out.
+fn hint(answer: &str, shown: usize) -> String {
+    answer
+        .chars()
+        .enumerate()
+        .map(|(i, c)| match i < shown || !c.is_alphanumeric() {
+            true => c,
+            false => '_',
+        })
+        .collect()
+}
+
+fn marked_path() -> Result<PathBuf, AskmeError> {
+    Ok(data_dir()?.join(MARKED_FILE_NAME))
+}
+
+#[derive(Serialize)]
+struct MarkRecord<'a> {
+    timestamp: u64,    // Unix time the question was marked at
+    set_file: &'a str, // Path to the set file
+    title: &'a str,    // Question title
+}
+
+/// Add `questions` to the list of questions marked for editing.
+fn mark(questions: &[Question]) -> Result<PathBuf, AskmeError> {
+    let path = marked_path()?;
+    if let Some(dir) = path.parent() {
+        if let Err(e) = fs::create_dir_all(dir) {
+            return Err(AskmeError::new(
+                format!("Failed to create '{}': {}", dir.display(), e),
+                Some(Box::from(e)),
+            ));
+        }
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let lines = questions
+        .iter()
+        .filter_map(|question| {
+            serde_json::to_string(&MarkRecord {
+                timestamp,
+                set_file: &question.source,
+                title: &question.title,
+            })
+            .ok()
+        })
+        .map(|line| line + "\n")
+        .collect::<String>();
+
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(lines.as_bytes()));
+
+    match result {
+        Ok(()) => Ok(path),
+        Err(e) => Err(AskmeError::new(
+            format!("Failed to write to '{}': {}", path.display(), e),
+            Some(Box::from(e)),
+        )),
+    }
+}
+
+fn print_help() {
+    ui::print(Tone::Bold, "Commands:");
+    for (name, _, description) in COMMANDS {
+        ui::print_spans(&[
+            Span::new(Tone::Highlight, format!(" :{:<7}", name)),
+            Span::plain(description),
+        ]);
+    }
+    ui::print(Tone::Plain, "");
+}
+
+/// Deal with `input` if it is a command.
+pub(crate) fn handle(input: &str) -> Handled {
+    let command = match Command::parse(input) {
+        None => return Handled::Answer,
+        Some(Ok(command)) => command,
+        Some(Err(name)) => {
+            print_warning(&format!("unknown command \":{}\", try :help", name));
+            return Handled::Again;
+        }
+    };
+
+    CONTEXT.with(|context| {
+        let mut context = context.borrow_mut();
+        let asking = !context.questions.is_empty();
+
+        match command {
+            Command::Help => print_help(),
+            Command::Skip => {
+                ui::print_spaced(Tone::Partial, "⏭ Skipped");
+                context.interrupt = Some(command);
+                return Handled::Interrupt;
+            }
+            Command::Quit => {
+                context.interrupt = Some(command);
+                context.quit = true;
+                return Handled::Interrupt;
+            }
+            Command::Hint | Command::Reveal | Command::Mark | Command::Score | Command::Undo
+                if !asking =>
+            {
+                print_warning("that command isn't available here");
+            }
+            // Every item of an ordering question is shown, so a hint would give away its place
+            Command::Hint
+                if context
+                    .questions
+                    .iter()
+                    .all(|question| question.kind == QuestionKind::Order) =>
+            {
+                print_warning("there are no hints for ordering questions");
+            }
+            Command::Hint => {
+                context.hints += 1;
+                let several = context.questions.len() > 1;
+                for question in context
+                    .questions
+                    .iter()
+                    .filter(|question| question.kind != QuestionKind::Order)
+                {
+                    let answer = question.answers.first().map(String::as_str);
+                    let label = match several {
+                        true => format!("Hint ({}): ", question.title),
+                        false => "Hint: ".to_string(),
+                    };
+                    ui::print_labelled(&label, &hint(answer.unwrap_or_default(), context.hints));
+                }
+            }
+            Command::Reveal => {
+                for question in &context.questions {
+                    ui::print_spans(&[
+                        Span::plain(format!(" {} → ", question.title)),
+                        Span::new(Tone::Emphasis, question.answers.join(", ")),
+                    ]);
+                }
+                ui::print(Tone::Plain, "");
+                context.interrupt = Some(command);
+                return Handled::Interrupt;
+            }
+            Command::Score => ui::print(
+                Tone::Highlight,
+                &format!(
+                    "Correct answers so far: {}/{} ({}/{} points)",
+                    format_score(context.so_far.correct),
+                    context.so_far.asked,
+                    format_score(context.so_far.points),
+                    format_score(context.so_far.max_points)
+                ),
+            ),
+            Command::Mark => match mark(&context.questions) {
+                Ok(path) => print_info(&format!("marked for editing in {}", path.display())),
+                Err(e) => print_warning(&format!("failed to mark the question: {}", e)),
+            },
+            Command::Undo if context.so_far.asked == 0 => print_warning("there is nothing to undo"),
+            Command::Undo => {
+                context.interrupt = Some(command);
+                return Handled::Interrupt;
+            }
+        }
+
+        Handled::Again
+    })
+}
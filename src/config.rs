@@ -0,0 +1,367 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Configuration file with default settings and named profiles.
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use crate::generate::Distractors;
+use crate::grading::{OrderInput, OrderScoring, SelectScoring};
+use crate::prelude::*;
+use crate::scoring::GradeScale;
+use crate::ui::UiKind;
+
+const CONFIG_FILE_NAME: &str = "config.yaml";
+const MAX_SECONDS: f64 = 365.0 * 24.0 * 60.0 * 60.0; // Longest duration or time limit (a year)
+
+/// Settings of a mode, any of which may be left unset. Each mode only uses
+/// the settings it knows about.
+#[derive(Deserialize, Clone, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Options {
+    #[serde(rename = "duration")]
+    pub wait_duration: Option<f64>, // Delay between questions (seconds)
+    #[serde(rename = "loop")]
+    pub loop_questions: Option<bool>, // Whether to ask the questions in a loop
+    pub shuffle: Option<bool>,        // Whether to shuffle the questions
+    pub case_sensitive: Option<bool>, // Whether answers are case-sensitive
+    pub show_correct: Option<bool>,   // Whether to show the answers to missed questions
+    pub max_choices: Option<usize>,   // Choices for each question (multichoice)
+    pub pairs: Option<usize>,         // Items to match in each round (match)
+    pub order_scoring: Option<OrderScoring>, // How ordering questions are scored
+    pub order_input: Option<OrderInput>, // How ordering questions are answered
+    pub select_all: Option<bool>,     // Whether multiple choice asks for every correct option
+    pub select_scoring: Option<SelectScoring>, // How "select all that apply" questions are scored
+    pub distractors: Option<Distractors>, // How the wrong options of multiple choice are picked
+    pub until_mastered: Option<u32>,  // Correct answers in a row needed to master a question
+    pub adaptive: Option<bool>,       // Whether to pick questions adaptively
+    pub count: Option<usize>,         // Number of questions to ask
+    pub question_time: Option<f64>,   // Time limit per question (seconds)
+    pub time_limit: Option<f64>,      // Time limit for the whole session (seconds)
+    pub exam: Option<bool>,           // Whether to run an exam
+    pub pass_mark: Option<f64>,       // Exam pass mark (percentage)
+    pub negative_marking: Option<f64>, // Share of the points lost for a wrong choice (multichoice)
+    pub hint_penalty: Option<f64>,    // Share of the credit lost for each hint taken
+    pub typo_credit: Option<f64>,     // Credit for an answer only off by a typo (memorize)
+    pub grades: Option<GradeScale>,   // Grades and the percentage each needs
+    pub ui: Option<UiKind>,           // Frontend to use
+}
+
+impl Options {
+    /// Layer `over` on top of these options: every setting `over` sets wins.
+    pub fn merge(&self, over: &Options) -> Options {
+        Options {
+            wait_duration: over.wait_duration.or(self.wait_duration),
+            loop_questions: over.loop_questions.or(self.loop_questions),
+            shuffle: over.shuffle.or(self.shuffle),
+            case_sensitive: over.case_sensitive.or(self.case_sensitive),
+            show_correct: over.show_correct.or(self.show_correct),
+            max_choices: over.max_choices.or(self.max_choices),
+            pairs: over.pairs.or(self.pairs),
+            order_scoring: over.order_scoring.or(self.order_scoring),
+            order_input: over.order_input.or(self.order_input),
+            select_all: over.select_all.or(self.select_all),
+            select_scoring: over.select_scoring.or(self.select_scoring),
+            distractors: over.distractors.or(self.distractors),
+            until_mastered: over.until_mastered.or(self.until_mastered),
+            adaptive: over.adaptive.or(self.adaptive),
+            count: over.count.or(self.count),
+            question_time: over.question_time.or(self.question_time),
+            time_limit: over.time_limit.or(self.time_limit),
+            exam: over.exam.or(self.exam),
+            pass_mark: over.pass_mark.or(self.pass_mark),
+            negative_marking: over.negative_marking.or(self.negative_marking),
+            hint_penalty: over.hint_penalty.or(self.hint_penalty),
+            typo_credit: over.typo_credit.or(self.typo_credit),
+            grades: over.grades.clone().or(self.grades.clone()),
+            ui: over.ui.or(self.ui),
+        }
+    }
+
+    /// Check the settings the command line would have rejected.
+    pub fn validate(&self) -> Result<(), AskmeError> {
+        let invalid = |setting: &str, expected: &str| {
+            Err(AskmeError::new(
+                format!("Invalid '{}' setting: expected {}", setting, expected),
+                None,
+            ))
+        };
+
+        // Negative, NaN and endless durations can't be waited for
+        if self
+            .wait_duration
+            .is_some_and(|d| !(0.0..=MAX_SECONDS).contains(&d))
+        {
+            return invalid("duration", "a positive number of seconds, up to a year");
+        }
+        let seconds = |value: Option<f64>| value.is_some_and(|v| !(v > 0.0 && v <= MAX_SECONDS));
+        if seconds(self.question_time) {
+            return invalid("question_time", "a number of seconds above 0, up to a year");
+        }
+        if seconds(self.time_limit) {
+            return invalid("time_limit", "a number of seconds above 0, up to a year");
+        }
+        if self.max_choices == Some(0) {
+            return invalid("max_choices", "at least 1");
+        }
+        if self.pairs.is_some_and(|n| !(2..=26).contains(&n)) {
+            return invalid("pairs", "a number from 2 to 26");
+        }
+        if self.until_mastered == Some(0) {
+            return invalid("until_mastered", "at least 1");
+        }
+        if self.count == Some(0) {
+            return invalid("count", "at least 1");
+        }
+        let fraction = |value: Option<f64>| value.is_some_and(|v| !(0.0..=1.0).contains(&v));
+        if fraction(self.negative_marking) {
+            return invalid("negative_marking", "a number from 0 to 1");
+        }
+        if fraction(self.hint_penalty) {
+            return invalid("hint_penalty", "a number from 0 to 1");
+        }
+        if fraction(self.typo_credit) {
+            return invalid("typo_credit", "a number from 0 to 1");
+        }
+        if self
+            .grades
+            .as_ref()
+            .is_some_and(|grades| grades.boundaries().is_empty())
+        {
+            return invalid("grades", "at least one grade");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub defaults: Options,                  // Settings for every mode
+    pub modes: HashMap<String, Options>,    // Settings for each mode, by mode name
+    pub profiles: HashMap<String, Options>, // Named settings picked with --profile
+}
+
+/// Where the configuration file is looked for (`$XDG_CONFIG_HOME/askme/config.yaml` on Linux).
+pub fn config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("askme").join(CONFIG_FILE_NAME))
+}
+
+impl Config {
+    /// Load the configuration file at `path`, or the default one if no path
+    /// is given. Only a missing default configuration file is not an error.
+    pub fn load(path: Option<&str>) -> Result<Config, AskmeError> {
+        let path = match path {
+            Some(path) => PathBuf::from(path),
+            None => match config_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Config::default()),
+            },
+        };
+
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                return Err(AskmeError::new(
+                    format!("Failed to read config file '{}': {}", path.display(), e),
+                    Some(Box::from(e)),
+                ))
+            }
+        };
+
+        match serde_yaml::from_str::<Option<Config>>(&content) {
+            Ok(config) => Ok(config.unwrap_or_default()),
+            Err(e) => Err(AskmeError::new(
+                format!("Failed to parse config file '{}': {}", path.display(), e),
+                Some(Box::from(e)),
+            )),
+        }
+    }
+
+    /// The settings of `mode`, from lowest to highest precedence: the
+    /// `defaults` section, the mode's section, the set file's settings, the
+    /// chosen profile and finally the command line.
+    pub fn resolve(
+        &self,
+        mode: &str,
+        profile: Option<&str>,
+        set: &Options,
+        cli: &Options,
+    ) -> Result<Options, AskmeError> {
+        let mut options = self.defaults.clone();
+
+        if let Some(mode_options) = self.modes.get(mode) {
+            options = options.merge(mode_options);
+        }
+
+        options = options.merge(set);
+
+        if let Some(name) = profile {
+            match self.profiles.get(name) {
+                Some(profile_options) => options = options.merge(profile_options),
+                None => {
+                    return Err(AskmeError::new(
+                        format!("No profile named '{}' in the config file", name),
+                        None,
+                    ))
+                }
+            }
+        }
+
+        options = options.merge(cli);
+        options.validate()?;
+
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(yaml: &str) -> Config {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    /// Every layer sets `count` to its own value; `max_choices` is only set
+    /// by the lower layers.
+    const CONFIG: &str = "
+defaults: { count: 1, max_choices: 2, shuffle: true }
+modes:
+  memorize: { count: 2, max_choices: 3 }
+profiles:
+  exam: { count: 4 }
+";
+
+    fn set() -> Options {
+        Options {
+            count: Some(3),
+            ..Options::default()
+        }
+    }
+
+    fn cli() -> Options {
+        Options {
+            count: Some(5),
+            ..Options::default()
+        }
+    }
+
+    #[test]
+    fn defaults_apply_without_other_layers() {
+        let options = config(CONFIG)
+            .resolve(
+                "multichoice",
+                None,
+                &Options::default(),
+                &Options::default(),
+            )
+            .unwrap();
+
+        assert_eq!(options.count, Some(1));
+        assert_eq!(options.max_choices, Some(2));
+        assert_eq!(options.shuffle, Some(true));
+    }
+
+    #[test]
+    fn mode_overrides_defaults() {
+        let options = config(CONFIG)
+            .resolve("memorize", None, &Options::default(), &Options::default())
+            .unwrap();
+
+        assert_eq!(options.count, Some(2));
+        assert_eq!(options.max_choices, Some(3));
+        assert_eq!(options.shuffle, Some(true));
+    }
+
+    #[test]
+    fn set_overrides_mode() {
+        let options = config(CONFIG)
+            .resolve("memorize", None, &set(), &Options::default())
+            .unwrap();
+
+        assert_eq!(options.count, Some(3));
+        assert_eq!(options.max_choices, Some(3));
+    }
+
+    #[test]
+    fn profile_overrides_set() {
+        let options = config(CONFIG)
+            .resolve("memorize", Some("exam"), &set(), &Options::default())
+            .unwrap();
+
+        assert_eq!(options.count, Some(4));
+        assert_eq!(options.max_choices, Some(3));
+    }
+
+    #[test]
+    fn cli_overrides_everything() {
+        let options = config(CONFIG)
+            .resolve("memorize", Some("exam"), &set(), &cli())
+            .unwrap();
+
+        assert_eq!(options.count, Some(5));
+        assert_eq!(options.max_choices, Some(3));
+        assert_eq!(options.shuffle, Some(true));
+    }
+
+    #[test]
+    fn unset_settings_stay_unset() {
+        let options = Config::default()
+            .resolve("memorize", None, &Options::default(), &Options::default())
+            .unwrap();
+
+        assert_eq!(options.count, None);
+        assert_eq!(options.shuffle, None);
+    }
+
+    #[test]
+    fn unknown_profile_is_an_error() {
+        let result = config(CONFIG).resolve("memorize", Some("nope"), &set(), &cli());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn merged_settings_are_validated() {
+        let set = Options {
+            time_limit: Some(-1.0),
+            ..Options::default()
+        };
+        assert!(config(CONFIG)
+            .resolve("memorize", None, &set, &Options::default())
+            .is_err());
+
+        for time in [f64::NAN, f64::INFINITY, 1e20] {
+            let cli = Options {
+                question_time: Some(time),
+                wait_duration: Some(1.0),
+                ..Options::default()
+            };
+            assert!(config(CONFIG)
+                .resolve("memorize", None, &Options::default(), &cli)
+                .is_err());
+
+            let cli = Options {
+                wait_duration: Some(time),
+                ..Options::default()
+            };
+            assert!(config(CONFIG)
+                .resolve("memorize", None, &Options::default(), &cli)
+                .is_err());
+        }
+    }
+}
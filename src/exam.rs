@@ -0,0 +1,301 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Exams: no feedback until every answer is submitted.
+use std::time::{Duration, Instant};
+
+use crate::prelude::*;
+use crate::scoring::Summary;
+use crate::ui::{self, Span, Tone};
+use crate::{commands, format_score, get_confirmation, get_select_input};
+
+/// Pass mark (percentage) used when neither the command line nor the set
+/// sets one.
+pub const DEFAULT_PASS_MARK: f64 = 50.0;
+
+/// Whether the points of `summary` reach `pass_mark` (a percentage).
+pub fn passes(summary: &Summary, pass_mark: f64) -> bool {
+    summary.percentage() >= pass_mark
+}
+
+/// A question as it appears in the exam review.
+pub struct ExamItem {
+    pub title: String,   // Question as shown to the user
+    pub correct: String, // Correct answer(s) as shown in the review
+    pub points: f64,     // Points the question is worth
+}
+
+/// Keeps track of the answers given during an exam, and lets the user skip
+/// questions and come back to them before submitting.
+///
+/// The mode asks the questions itself: it takes questions with
+/// `next_question` and hands the answers back with `answer`.
+pub struct Exam {
+    items: Vec<ExamItem>,
+    answers: Vec<Option<String>>, // None if the question was skipped
+    times: Vec<Duration>,         // Time spent on each question
+    next: usize,                  // Next question of the first pass
+    asked_at: Instant,
+}
+
+impl Exam {
+    pub fn new(items: Vec<ExamItem>) -> Self {
+        let len = items.len();
+
+        Exam {
+            items,
+            answers: vec![None; len],
+            times: vec![Duration::ZERO; len],
+            next: 0,
+            asked_at: Instant::now(),
+        }
+    }
+
+    /// Index of the next question to ask, or `None` once the user submits.
+    ///
+    /// The questions are first asked in order; after that the user can pick
+    /// questions to revisit until they confirm submitting.
+    pub fn next_question(&mut self) -> Option<usize> {
+        // Quitting hands the exam in as it is
+        if commands::has_quit() {
+            return None;
+        }
+
+        self.asked_at = Instant::now();
+        ui::show_progress(self.items.len() - self.unanswered_count(), self.items.len());
+
+        if self.next < self.items.len() {
+            self.next += 1;
+            return Some(self.next - 1);
+        }
+
+        loop {
+            let mut options = self
+                .items
+                .iter()
+                .zip(&self.answers)
+                .enumerate()
+                .map(|(i, (item, answer))| match answer {
+                    Some(answer) => format!("{}. {} — {}", i + 1, item.title, answer),
+                    None => format!("{}. {} — (skipped)", i + 1, item.title),
+                })
+                .collect::<Vec<String>>();
+            options.push("Submit answers".to_string());
+
+            let choice = get_select_input("Pick a question to revisit, or submit:", options)?;
+            if choice < self.items.len() {
+                self.asked_at = Instant::now();
+                return Some(choice);
+            }
+
+            let message = match self.unanswered_count() {
+                0 => "Submit your answers?".to_string(),
+                1 => "Submit with 1 unanswered question?".to_string(),
+                n => format!("Submit with {} unanswered questions?", n),
+            };
+            if get_confirmation(&message)? {
+                return None;
+            }
+        }
+    }
+
+    /// Record the answer to the question taken with `next_question`.
+    pub fn answer(&mut self, idx: usize, answer: Option<String>) {
+        self.times[idx] += self.asked_at.elapsed();
+
+        // Revisiting a question and skipping it keeps the earlier answer
+        if answer.is_some() {
+            self.answers[idx] = answer;
+        }
+    }
+
+    pub fn answers(&self) -> &[Option<String>] {
+        &self.answers
+    }
+
+    pub fn unanswered_count(&self) -> usize {
+        self.answers.iter().filter(|a| a.is_none()).count()
+    }
+
+    /// Turn the graded answers into results, with the score and the points
+    /// earned for each question.
+    pub fn results(&self, scores: &[f64], points: &[f64]) -> Vec<QuestionResult> {
+        self.items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| QuestionResult {
+                title: item.title.clone(),
+                answer: self.answers[i].clone(),
+                score: scores[i],
+                points: points[i],
+                max_points: item.points,
+                response_time: self.times[i],
+                timed_out: false,
+            })
+            .collect()
+    }
+
+    /// Print every question with the user's answer and the correct answers,
+    /// then the percentage of the points earned and whether it passes
+    /// `pass_mark` (a percentage). `results` come from `results`.
+    pub fn print_review(&self, results: &[QuestionResult], pass_mark: f64) {
+        let headers = ["#", "Question", "Your answer", "Correct answer"];
+        let rows = self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                [
+                    (i + 1).to_string(),
+                    item.title.clone(),
+                    self.answers[i].clone().unwrap_or_else(|| "-".to_string()),
+                    item.correct.clone(),
+                ]
+            })
+            .collect::<Vec<[String; 4]>>();
+
+        let widths = (0..headers.len())
+            .map(|col| {
+                rows.iter()
+                    .map(|row| row[col].chars().count())
+                    .chain([headers[col].len()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<usize>>();
+
+        let header = headers
+            .iter()
+            .zip(&widths)
+            .map(|(h, &w)| format!("{:<w$}", h))
+            .collect::<Vec<String>>()
+            .join("  ");
+        ui::print(Tone::Bold, &format!(" {}", header));
+
+        for (row, result) in rows.iter().zip(results) {
+            let cells = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &w)| format!("{:<w$}", cell))
+                .collect::<Vec<String>>()
+                .join("  ");
+            let mark = match result.score {
+                s if s >= 1.0 => Span::new(Tone::Correct, "✔️"),
+                s if s > 0.0 => Span::new(Tone::Partial, "➖"),
+                _ => Span::new(Tone::Incorrect, "❌"),
+            };
+            ui::print_spans(&[Span::plain(format!(" {}  ", cells)), mark]);
+        }
+        ui::print(Tone::Plain, "");
+
+        let summary = Summary::from_results(results);
+        let percentage = summary.percentage();
+        let verdict = match passes(&summary, pass_mark) {
+            true => Span::new(Tone::Correct, "PASSED"),
+            false => Span::new(Tone::Incorrect, "FAILED"),
+        };
+
+        ui::print_spans(&[
+            Span::plain(" "),
+            Span::new(Tone::Highlight, format!("Score: {:.1}%", percentage)),
+            Span::plain(format!(
+                " ({}/{} points, pass mark {}%) ",
+                format_score(summary.points),
+                format_score(summary.max_points),
+                format_score(pass_mark)
+            )),
+            verdict,
+        ]);
+        ui::print(Tone::Plain, "");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::{set_scripted_ui, ScriptedUi};
+
+    fn exam(len: usize) -> Exam {
+        let items = (1..=len)
+            .map(|n| ExamItem {
+                title: format!("Q{}", n),
+                correct: format!("A{}", n),
+                points: n as f64,
+            })
+            .collect();
+        Exam::new(items)
+    }
+
+    /// Go through the first pass, answering each question with `answers`.
+    fn first_pass(exam: &mut Exam, answers: &[Option<&str>]) {
+        for answer in answers {
+            let idx = exam.next_question().unwrap();
+            exam.answer(idx, answer.map(String::from));
+        }
+    }
+
+    #[test]
+    fn skipped_questions_can_be_revisited_before_submitting() {
+        let mut exam = exam(3);
+        first_pass(&mut exam, &[Some("A1"), None, Some("no")]);
+        assert_eq!(exam.unanswered_count(), 1);
+
+        // Revisit Q2, then decline to submit once before submitting
+        set_scripted_ui(ScriptedUi {
+            selects: [1, 3, 3].into(),
+            confirms: [false, true].into(),
+        });
+        assert_eq!(exam.next_question(), Some(1));
+        exam.answer(1, Some("A2".to_string()));
+        assert_eq!(exam.next_question(), None);
+
+        assert_eq!(exam.unanswered_count(), 0);
+        assert_eq!(
+            exam.answers(),
+            [Some("A1"), Some("A2"), Some("no")].map(|a| a.map(String::from))
+        );
+    }
+
+    #[test]
+    fn skipping_a_revisited_question_keeps_its_answer() {
+        let mut exam = exam(2);
+        first_pass(&mut exam, &[Some("A1"), Some("A2")]);
+
+        set_scripted_ui(ScriptedUi {
+            selects: [0, 2].into(),
+            confirms: [true].into(),
+        });
+        assert_eq!(exam.next_question(), Some(0));
+        exam.answer(0, None);
+        assert_eq!(exam.next_question(), None);
+        assert_eq!(exam.answers()[0].as_deref(), Some("A1"));
+    }
+
+    #[test]
+    fn the_grade_counts_the_points_of_every_question() {
+        let mut exam = exam(3);
+        first_pass(&mut exam, &[Some("A1"), Some("A2"), None]);
+
+        // Q1 and Q2 are right, worth 3 of the 6 points
+        let results = exam.results(&[1.0, 1.0, 0.0], &[1.0, 2.0, 0.0]);
+        assert_eq!(results[2].answer, None);
+        assert_eq!(results[2].max_points, 3.0);
+
+        let summary = Summary::from_results(&results);
+        assert_eq!(summary.percentage(), 50.0);
+        assert!(passes(&summary, 50.0));
+        assert!(!passes(&summary, 50.1));
+        assert!(passes(&summary, DEFAULT_PASS_MARK));
+    }
+}
@@ -0,0 +1,326 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Generating multiple choice and true/false questions from a set.
+use std::collections::HashSet;
+
+use clap::ValueEnum;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::grading::edit_distance;
+use crate::prelude::*;
+use crate::print_warning;
+use crate::random::rng;
+
+/// How the wrong options of a multiple choice question are picked.
+#[derive(Clone, Copy, Default, PartialEq, Debug, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Distractors {
+    #[default]
+    Random, // Any answer of the set
+    Tags,    // Answers of questions sharing the most tags
+    Shape,   // Answers of similar length and characters (letters, digits...)
+    Similar, // Answers spelled the most alike
+    Type,    // Answers of the same type: numbers for numbers, words for words
+}
+
+/// Rough type of an answer.
+#[derive(PartialEq)]
+enum AnswerType {
+    Number, // Such as "42", "-1.5" or "1,000"
+    Word,   // Letters and spaces only
+    Other,  // Anything else, such as "H2O"
+}
+
+impl AnswerType {
+    fn of(answer: &str) -> Self {
+        let answer = answer.trim();
+
+        if answer.replace(',', "").parse::<f64>().is_ok() {
+            AnswerType::Number
+        } else if answer
+            .chars()
+            .all(|c| c.is_alphabetic() || c.is_whitespace())
+        {
+            AnswerType::Word
+        } else {
+            AnswerType::Other
+        }
+    }
+}
+
+/// Share of the characters of `answer` that are letters, digits and anything
+/// else.
+fn char_classes(answer: &str) -> [f64; 3] {
+    let len = answer.chars().count().max(1) as f64;
+    let count = |f: fn(&char) -> bool| answer.chars().filter(f).count() as f64 / len;
+
+    [
+        count(|c| c.is_alphabetic()),
+        count(|c| c.is_numeric()),
+        count(|c| !c.is_alphanumeric()),
+    ]
+}
+
+impl Distractors {
+    /// How far `candidate`, an answer of `candidate_question`, is from being a
+    /// plausible wrong option for `question`: the lower, the better.
+    fn distance(&self, question: &Question, candidate_question: &Question, candidate: &str) -> f64 {
+        let closest = |distance: &dyn Fn(&str) -> f64| {
+            question
+                .answers
+                .iter()
+                .map(|answer| distance(answer))
+                .fold(f64::MAX, f64::min)
+        };
+
+        match self {
+            Distractors::Random => 0.0,
+            Distractors::Tags => {
+                let shared = question
+                    .tags
+                    .iter()
+                    .filter(|tag| candidate_question.tags.contains(tag))
+                    .count();
+                -(shared as f64)
+            }
+            Distractors::Shape => closest(&|answer| {
+                let len = |text: &str| text.chars().count() as f64;
+                let classes = char_classes(answer)
+                    .iter()
+                    .zip(char_classes(candidate))
+                    .map(|(a, b)| (a - b).abs())
+                    .sum::<f64>();
+                (len(answer) - len(candidate)).abs() / len(answer).max(1.0) + classes
+            }),
+            Distractors::Similar => closest(&|answer| {
+                edit_distance(&answer.to_lowercase(), &candidate.to_lowercase()) as f64
+            }),
+            Distractors::Type => closest(&|answer| match AnswerType::of(answer)
+                == AnswerType::of(candidate)
+            {
+                true => 0.0,
+                false => 1.0,
+            }),
+        }
+    }
+}
+
+/// What tells options apart: answers differing only in case or surrounding
+/// spaces, like "Paris" and "paris ", are the same option.
+fn option_key(answer: &str) -> String {
+    answer.trim().to_lowercase()
+}
+
+/// Whether `answer` is one of the answers of `question`, as an option.
+fn is_answer_of(question: &Question, answer: &str) -> bool {
+    let key = option_key(answer);
+    question.answers.iter().any(|own| option_key(own) == key)
+}
+
+/// Every distinct wrong option for `question` out of the answers of
+/// `questions`, in random order, with how far each is from being plausible
+/// according to `strategy` (see `Distractors::distance`).
+pub fn distractor_candidates(
+    question: &Question,
+    questions: &[Question],
+    strategy: Distractors,
+) -> Vec<(f64, String)> {
+    let mut candidates = questions
+        .iter()
+        .flat_map(|qn| qn.answers.iter().map(move |answer| (qn, answer)))
+        .filter(|(_, answer)| !is_answer_of(question, answer))
+        .collect::<Vec<(&Question, &String)>>();
+    candidates.shuffle(&mut rng());
+
+    let mut seen = HashSet::new();
+    candidates.retain(|(_, answer)| seen.insert(option_key(answer)));
+
+    candidates
+        .into_iter()
+        .map(|(qn, answer)| (strategy.distance(question, qn, answer), answer.clone()))
+        .collect()
+}
+
+/// Pick up to `count` distinct wrong options for `question` out of the
+/// answers of `questions`, the most plausible ones according to `strategy`.
+fn distractors(
+    question: &Question,
+    questions: &[Question],
+    count: usize,
+    strategy: Distractors,
+) -> Vec<String> {
+    // Sorting is stable, so equally plausible candidates stay shuffled
+    let mut ranked = distractor_candidates(question, questions, strategy);
+    ranked.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    ranked
+        .into_iter()
+        .take(count)
+        .map(|(_, answer)| answer)
+        .collect()
+}
+
+/// Number of distinct wrong options `questions` have for `question`.
+fn distractors_available(question: &Question, questions: &[Question]) -> usize {
+    questions
+        .iter()
+        .flat_map(|qn| &qn.answers)
+        .filter(|answer| !is_answer_of(question, answer))
+        .map(|answer| option_key(answer))
+        .collect::<HashSet<String>>()
+        .len()
+}
+
+/// Most choices `multiple_choices` can give `question`: one of its answers
+/// and every distinct wrong one.
+pub fn choices_available(question: &Question, questions: &[Question]) -> usize {
+    distractors_available(question, questions) + 1
+}
+
+/// Most choices `multiple_select_choices` can give `question`: each of its
+/// distinct answers and every distinct wrong one.
+pub fn select_choices_available(question: &Question, questions: &[Question]) -> usize {
+    let answers = question
+        .answers
+        .iter()
+        .map(|answer| option_key(answer))
+        .collect::<HashSet<String>>();
+    answers.len() + distractors_available(question, questions)
+}
+
+/// Warn that `question` can't be given the `len` choices asked for when only
+/// `available` can be made up. The generators don't warn themselves, so that
+/// callers that cap the choices (or can't print) stay quiet.
+pub fn warn_few_choices(question: &Question, available: usize, len: usize) {
+    if available < len {
+        print_warning(&format!(
+            "\"{}\" only has {} distinct choices, fewer than the {} asked for",
+            question.title, available, len
+        ));
+    }
+}
+
+/// Randomly generate a list of up to `len` (but at least one) choices for
+/// `question`, which must have an answer, picking the wrong ones from the
+/// answers of `questions` with `strategy`. Returns the choices and the index
+/// of the correct one.
+pub fn multiple_choices(
+    question: &Question,
+    questions: &[Question],
+    len: usize,
+    strategy: Distractors,
+) -> (Vec<String>, usize) {
+    let mut rng = rng();
+
+    let available = choices_available(question, questions);
+    let len = len.max(1);
+
+    let correct_answer = question
+        .answers
+        .choose(&mut rng)
+        .cloned()
+        .unwrap_or_default();
+
+    let mut choices = distractors(question, questions, len.min(available) - 1, strategy);
+
+    let correct_location = rng.gen_range(0..=choices.len());
+    choices.insert(correct_location, correct_answer);
+
+    (choices, correct_location)
+}
+
+/// Randomly generate up to `len` (but at least one) choices for a "select
+/// all that apply" question, which must have an answer: some of its answers
+/// (at least one, and at least one wrong choice when the set has any), with
+/// the wrong ones picked from the answers of `questions` with `strategy`.
+/// Returns the choices and the indices of the correct ones.
+pub fn multiple_select_choices(
+    question: &Question,
+    questions: &[Question],
+    len: usize,
+    strategy: Distractors,
+) -> (Vec<String>, Vec<usize>) {
+    let mut rng = rng();
+
+    let mut seen = HashSet::new();
+    let mut answers = question.answers.clone();
+    answers.retain(|answer| seen.insert(option_key(answer)));
+    answers.shuffle(&mut rng);
+
+    let wrong_len = distractors_available(question, questions);
+
+    let available = answers.len() + wrong_len;
+    let len = len.max(1);
+
+    let len = len.min(available);
+    let most_correct = match wrong_len {
+        0 => len,
+        _ => len.saturating_sub(1).max(1),
+    };
+    let correct_len = rng.gen_range(1..=most_correct.min(answers.len()));
+
+    answers.truncate(correct_len);
+    let wrong = distractors(question, questions, len - correct_len, strategy);
+
+    let mut choices = answers
+        .into_iter()
+        .map(|answer| (answer, true))
+        .chain(wrong.into_iter().map(|answer| (answer, false)))
+        .collect::<Vec<(String, bool)>>();
+    choices.shuffle(&mut rng);
+
+    let correct = choices
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, is_correct))| *is_correct)
+        .map(|(idx, _)| idx)
+        .collect();
+
+    (
+        choices.into_iter().map(|(choice, _)| choice).collect(),
+        correct,
+    )
+}
+
+/// Pick a random answer of `question`, or of any of `questions`.
+fn random_answer(question: Option<&Question>, questions: &[Question]) -> String {
+    let answers = match question {
+        Some(question) => question.answers.iter().collect::<Vec<&String>>(),
+        None => questions
+            .iter()
+            .flat_map(|qn| &qn.answers)
+            .collect::<Vec<&String>>(),
+    };
+
+    match answers.choose(&mut rng()) {
+        Some(answer) => answer.to_string(),
+        None => panic!("Failed to get random answer from set!"),
+    }
+}
+
+/// Generate the statement "`question` is ..." for a true/false question:
+/// half of the time with one of its own answers, otherwise with any answer
+/// of `questions`. Returns the answer used and whether the statement is true.
+pub fn true_false_statement(question: &Question, questions: &[Question]) -> (String, bool) {
+    let answer = match rng().gen_bool(0.5) {
+        true => random_answer(Some(question), questions),
+        false => random_answer(None, questions),
+    };
+    let truth = is_answer_of(question, &answer);
+
+    (answer, truth)
+}
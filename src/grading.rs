@@ -0,0 +1,513 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Answer checking shared by the askme modes.
+use crate::prelude::*;
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// How an ordering question is scored.
+#[derive(Clone, Copy, Debug, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderScoring {
+    Exact,    // Full credit only for the exact order
+    Position, // Credit for each item in its correct position
+    Kendall,  // Credit for each pair of items in the correct relative order
+}
+
+/// How "select all that apply" questions are scored.
+#[derive(Clone, Copy, Debug, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SelectScoring {
+    Exact,   // Full credit only for picking exactly the correct options
+    Partial, // Credit for each correct option picked, less each wrong one
+}
+
+/// How ordering questions are answered.
+#[derive(Clone, Copy, Debug, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderInput {
+    Letters, // Type the letters of the items in order
+    Pick,    // Pick the items one by one from a list
+}
+
+/// Result of grading a list question.
+pub struct ListGrade {
+    pub matched: Vec<String>, // Expected items the user got right
+    pub missed: Vec<String>,  // Expected items the user missed (or misplaced)
+    pub extra: Vec<String>,   // Items the user gave that are not part of the answer
+    pub score: f64,           // Partial credit, from 0.0 to 1.0
+}
+
+impl ListGrade {
+    pub fn is_correct(&self) -> bool {
+        self.missed.is_empty() && self.extra.is_empty()
+    }
+}
+
+fn normalize(text: &str, case_sensitive: bool) -> String {
+    match case_sensitive {
+        true => text.trim().to_string(),
+        false => text.trim().to_lowercase(),
+    }
+}
+
+/// Check whether `user_answer` is one of the question's answers.
+pub fn check_answer(question: &Question, user_answer: &str, case_sensitive: bool) -> bool {
+    let user_answer = normalize(user_answer, case_sensitive);
+
+    question
+        .answers
+        .iter()
+        .any(|a| normalize(a, case_sensitive) == user_answer)
+}
+
+/// Number of single-character edits turning `a` into `b`.
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<char>>();
+    let mut row = (0..=b.len()).collect::<Vec<usize>>();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+
+    row[b.len()]
+}
+
+/// Whether `user_answer` is only off from `answer` by a typo: a few edits
+/// for longer answers, none for short ones.
+fn is_typo(answer: &str, user_answer: &str) -> bool {
+    let len = answer.chars().count();
+    let allowed = match len {
+        0..=3 => 0,
+        4..=9 => 1,
+        _ => len / 5,
+    };
+
+    edit_distance(answer, user_answer) <= allowed
+}
+
+/// Grade a typed answer: full credit for one of the question's answers, and
+/// `typo_credit` for one that is only off by a typo.
+pub fn grade_answer(
+    question: &Question,
+    user_answer: &str,
+    case_sensitive: bool,
+    typo_credit: f64,
+) -> f64 {
+    if check_answer(question, user_answer, case_sensitive) {
+        return 1.0;
+    }
+
+    let user_answer = normalize(user_answer, case_sensitive);
+    let typo = question
+        .answers
+        .iter()
+        .any(|a| is_typo(&normalize(a, case_sensitive), &user_answer));
+
+    match typo {
+        true => typo_credit,
+        false => 0.0,
+    }
+}
+
+/// Split a comma-separated answer into its (non-empty) items.
+pub fn split_list_answer(input: &str) -> Vec<String> {
+    input
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Grade a list question, giving one share of credit per correct item.
+///
+/// If the question is ordered, an item only counts when it is given at the
+/// same position as in the answer list. Extra items count against the score
+/// so listing everything one can think of doesn't earn full credit.
+pub fn grade_list(question: &Question, items: &[String], case_sensitive: bool) -> ListGrade {
+    let expected = &question.answers;
+    let mut matched = Vec::new();
+    let mut missed = Vec::new();
+    let mut extra = Vec::new();
+
+    if question.ordered {
+        for (idx, answer) in expected.iter().enumerate() {
+            match items.get(idx) {
+                Some(item)
+                    if normalize(item, case_sensitive) == normalize(answer, case_sensitive) =>
+                {
+                    matched.push(answer.clone())
+                }
+                _ => missed.push(answer.clone()),
+            }
+        }
+
+        extra.extend(items.iter().skip(expected.len()).cloned());
+    } else {
+        let mut remaining = expected.clone();
+
+        for item in items {
+            let normalized = normalize(item, case_sensitive);
+            match remaining
+                .iter()
+                .position(|a| normalize(a, case_sensitive) == normalized)
+            {
+                Some(idx) => matched.push(remaining.remove(idx)),
+                None => extra.push(item.clone()),
+            }
+        }
+
+        missed = remaining;
+    }
+
+    let score = match expected.len() + extra.len() {
+        0 => 0.0,
+        total => matched.len() as f64 / total as f64,
+    };
+
+    ListGrade {
+        matched,
+        missed,
+        extra,
+        score,
+    }
+}
+
+/// Labels of `len` options: letters, or numbers from 1 when there are more
+/// options than letters.
+pub fn option_labels(len: usize) -> Vec<String> {
+    match len <= 26 {
+        true => ('a'..='z').take(len).map(String::from).collect(),
+        false => (1..=len).map(|n| n.to_string()).collect(),
+    }
+}
+
+/// Read the option picked out of `options`, given by its label (see
+/// `option_labels`), its number or the option itself.
+pub fn parse_option(input: &str, options: &[String]) -> Option<usize> {
+    let input = input.trim().to_lowercase();
+
+    option_labels(options.len())
+        .iter()
+        .position(|label| *label == input)
+        .or_else(|| match input.parse::<usize>() {
+            Ok(n) if (1..=options.len()).contains(&n) => Some(n - 1),
+            _ => None,
+        })
+        .or_else(|| {
+            options
+                .iter()
+                .position(|o| o.trim().to_lowercase() == input)
+        })
+}
+
+/// Read several options picked out of `len`, such as "ac", "a, c" or "1 3".
+/// Returns the indices in order, or `None` if nothing valid was given.
+pub fn parse_options(input: &str, len: usize) -> Option<Vec<usize>> {
+    let labels = option_labels(len);
+    let label_idx = |label: &str| labels.iter().position(|l| l == label);
+
+    let mut picked = Vec::new();
+    for token in input
+        .to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|token| !token.is_empty())
+    {
+        match label_idx(token) {
+            Some(idx) => picked.push(idx),
+            None => match token.parse::<usize>() {
+                Ok(n) if (1..=len).contains(&n) => picked.push(n - 1),
+                Ok(_) => return None,
+                // Letters can also be run together
+                Err(_) => {
+                    for c in token.chars() {
+                        picked.push(label_idx(&c.to_string())?);
+                    }
+                }
+            },
+        }
+    }
+    picked.sort();
+    picked.dedup();
+
+    (!picked.is_empty()).then_some(picked)
+}
+
+/// Parse an ordering answer such as "cab", "c a b" or "c, a, b" into item
+/// indices, or numbers such as "3 1 2" past 26 items (see `option_labels`).
+/// Returns `None` unless the labels are a permutation of the first
+/// `items_total` ones.
+pub fn parse_order(input: &str, items_total: usize) -> Option<Vec<usize>> {
+    let order = match items_total <= 26 {
+        true => input
+            .chars()
+            .filter(|c| !(c.is_whitespace() || *c == ','))
+            .map(|c| match c.to_ascii_lowercase() {
+                c @ 'a'..='z' => Some(c as usize - 'a' as usize),
+                _ => None,
+            })
+            .collect::<Option<Vec<usize>>>()?,
+        false => input
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|token| !token.is_empty())
+            .map(|token| token.parse::<usize>().ok()?.checked_sub(1))
+            .collect::<Option<Vec<usize>>>()?,
+    };
+
+    let mut seen = vec![false; items_total];
+    for &idx in &order {
+        if idx >= items_total || seen[idx] {
+            return None;
+        }
+        seen[idx] = true;
+    }
+
+    match order.len() == items_total {
+        true => Some(order),
+        false => None,
+    }
+}
+
+/// Grade an ordering answer. `given` holds the correct position of each item,
+/// in the order the user put them.
+pub fn grade_order(given: &[usize], scoring: OrderScoring) -> f64 {
+    let len = given.len();
+    if len == 0 {
+        return 0.0;
+    }
+
+    match scoring {
+        OrderScoring::Exact => match given.iter().enumerate().all(|(i, &pos)| i == pos) {
+            true => 1.0,
+            false => 0.0,
+        },
+        OrderScoring::Position => {
+            let placed = given.iter().enumerate().filter(|(i, &pos)| *i == pos);
+            placed.count() as f64 / len as f64
+        }
+        OrderScoring::Kendall => {
+            if len == 1 {
+                return 1.0;
+            }
+
+            let pairs = len * (len - 1) / 2;
+            let discordant = (0..len)
+                .flat_map(|i| (i + 1..len).map(move |j| (i, j)))
+                .filter(|&(i, j)| given[i] > given[j])
+                .count();
+
+            1.0 - discordant as f64 / pairs as f64
+        }
+    }
+}
+
+/// Grade the options picked for a "select all that apply" question, given
+/// the indices of the correct ones.
+pub fn grade_selection(picked: &[usize], correct: &[usize], scoring: SelectScoring) -> f64 {
+    if correct.is_empty() {
+        return 0.0;
+    }
+
+    let right = picked.iter().filter(|idx| correct.contains(idx)).count();
+    let wrong = picked.len() - right;
+
+    match scoring {
+        SelectScoring::Exact => match right == correct.len() && wrong == 0 {
+            true => 1.0,
+            false => 0.0,
+        },
+        SelectScoring::Partial => (right as f64 - wrong as f64).max(0.0) / correct.len() as f64,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(len: usize) -> Vec<String> {
+        (1..=len).map(|n| format!("Option {}", n)).collect()
+    }
+
+    fn list_question(answers: &[&str], ordered: bool) -> Question {
+        Question {
+            title: "List".to_string(),
+            answers: answers.iter().map(|a| a.to_string()).collect(),
+            kind: QuestionKind::List,
+            ordered,
+            tags: Vec::new(),
+            weight: 1.0,
+            points: 1.0,
+            select_all: None,
+            source: String::new(),
+        }
+    }
+
+    fn items(items: &[&str]) -> Vec<String> {
+        items.iter().map(|item| item.to_string()).collect()
+    }
+
+    #[test]
+    fn unordered_lists_take_the_items_in_any_order() {
+        let question = list_question(&["Helium", "Neon", "Argon"], false);
+
+        let grade = grade_list(&question, &items(&["argon", "Helium", "Neon"]), false);
+        assert!(grade.is_correct());
+        assert_eq!(grade.score, 1.0);
+
+        // Each answer is only matched once, so repeating one doesn't count twice
+        let grade = grade_list(&question, &items(&["Neon", "Neon"]), false);
+        assert_eq!(grade.matched, vec!["Neon"]);
+        assert_eq!(grade.missed, vec!["Helium", "Argon"]);
+        assert_eq!(grade.extra, vec!["Neon"]);
+        assert_eq!(grade.score, 1.0 / 4.0);
+
+        let grade = grade_list(&question, &items(&["neon"]), true);
+        assert_eq!(grade.score, 0.0);
+    }
+
+    #[test]
+    fn unordered_lists_match_repeated_answers_once_each() {
+        let question = list_question(&["Na", "Na", "Cl"], false);
+
+        let grade = grade_list(&question, &items(&["Na", "Cl", "Na"]), false);
+        assert!(grade.is_correct());
+
+        let grade = grade_list(&question, &items(&["Na", "Cl"]), false);
+        assert_eq!(grade.missed, vec!["Na"]);
+        assert_eq!(grade.score, 2.0 / 3.0);
+    }
+
+    #[test]
+    fn ordered_lists_only_count_items_in_their_place() {
+        let question = list_question(&["Hydrogen", "Helium", "Lithium"], true);
+
+        let grade = grade_list(&question, &items(&["Hydrogen", "Helium", "Lithium"]), false);
+        assert!(grade.is_correct());
+
+        let grade = grade_list(&question, &items(&["Hydrogen", "Lithium", "Helium"]), false);
+        assert_eq!(grade.matched, vec!["Hydrogen"]);
+        assert_eq!(grade.missed, vec!["Helium", "Lithium"]);
+        assert!(grade.extra.is_empty());
+        assert_eq!(grade.score, 1.0 / 3.0);
+    }
+
+    #[test]
+    fn extra_items_lower_the_score() {
+        for ordered in [false, true] {
+            let question = list_question(&["Helium", "Neon"], ordered);
+
+            let grade = grade_list(&question, &items(&["Helium", "Neon", "Xenon"]), false);
+            assert!(!grade.is_correct());
+            assert_eq!(grade.missed, Vec::<String>::new());
+            assert_eq!(grade.extra, vec!["Xenon"]);
+            assert_eq!(grade.score, 2.0 / 3.0);
+        }
+    }
+
+    #[test]
+    fn orders_are_scored_exactly_by_position_or_by_pairs() {
+        let right = [0, 1, 2, 3];
+        let reversed = [3, 2, 1, 0];
+        let swapped = [1, 0, 2, 3];
+
+        for scoring in [
+            OrderScoring::Exact,
+            OrderScoring::Position,
+            OrderScoring::Kendall,
+        ] {
+            assert_eq!(grade_order(&right, scoring), 1.0);
+            assert_eq!(grade_order(&[], scoring), 0.0);
+        }
+
+        assert_eq!(grade_order(&swapped, OrderScoring::Exact), 0.0);
+        assert_eq!(grade_order(&swapped, OrderScoring::Position), 0.5);
+        assert_eq!(grade_order(&reversed, OrderScoring::Position), 0.0);
+
+        // One adjacent swap puts one of the 6 pairs out of order
+        assert_eq!(grade_order(&reversed, OrderScoring::Kendall), 0.0);
+        assert_eq!(
+            grade_order(&swapped, OrderScoring::Kendall),
+            1.0 - 1.0 / 6.0
+        );
+        assert_eq!(grade_order(&[0], OrderScoring::Kendall), 1.0);
+    }
+
+    #[test]
+    fn options_are_picked_by_label_number_or_text() {
+        assert_eq!(parse_option("B", &options(3)), Some(1));
+        assert_eq!(parse_option("3", &options(3)), Some(2));
+        assert_eq!(parse_option(" option 1 ", &options(3)), Some(0));
+        assert_eq!(parse_option("d", &options(3)), None);
+
+        // Past 26 options, the labels are numbers
+        assert_eq!(parse_option("27", &options(30)), Some(26));
+        assert_eq!(parse_option("a", &options(30)), None);
+    }
+
+    #[test]
+    fn orders_are_letters_or_numbers_past_26_items() {
+        assert_eq!(parse_order("cab", 3), Some(vec![2, 0, 1]));
+        assert_eq!(parse_order("C, a b", 3), Some(vec![2, 0, 1]));
+        assert_eq!(parse_order("ca", 3), None);
+        assert_eq!(parse_order("caa", 3), None);
+
+        let mut numbers = (1..=27).rev().map(|n| n.to_string()).collect::<Vec<_>>();
+        let expected = (0..27).rev().collect::<Vec<usize>>();
+        assert_eq!(parse_order(&numbers.join(" "), 27), Some(expected));
+        numbers[0] = "0".to_string();
+        assert_eq!(parse_order(&numbers.join(","), 27), None);
+    }
+
+    #[test]
+    fn exact_selections_take_every_correct_option_and_nothing_else() {
+        let correct = [0, 2];
+        let exact = |picked: &[usize]| grade_selection(picked, &correct, SelectScoring::Exact);
+
+        assert_eq!(exact(&[0, 2]), 1.0);
+        assert_eq!(exact(&[2, 0]), 1.0);
+        assert_eq!(exact(&[0]), 0.0);
+        assert_eq!(exact(&[0, 1, 2]), 0.0);
+        assert_eq!(exact(&[]), 0.0);
+    }
+
+    #[test]
+    fn partial_selections_lose_a_correct_pick_for_each_wrong_one() {
+        let correct = [0, 2, 3, 5];
+        let partial = |picked: &[usize]| grade_selection(picked, &correct, SelectScoring::Partial);
+
+        assert_eq!(partial(&[0, 2, 3, 5]), 1.0);
+        assert_eq!(partial(&[0, 2]), 0.5);
+        assert_eq!(partial(&[0, 2, 3, 5, 1]), 0.75);
+        assert_eq!(partial(&[0, 2, 1]), 0.25);
+        assert_eq!(partial(&[0, 1]), 0.0);
+        assert_eq!(partial(&[]), 0.0);
+    }
+
+    #[test]
+    fn partial_selections_do_not_go_below_zero() {
+        let correct = [0];
+        let partial = |picked: &[usize]| grade_selection(picked, &correct, SelectScoring::Partial);
+
+        assert_eq!(partial(&[1, 2, 3]), 0.0);
+        assert_eq!(partial(&[0, 1, 2]), 0.0);
+        assert_eq!(grade_selection(&[0], &[], SelectScoring::Partial), 0.0);
+    }
+}
@@ -0,0 +1,287 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// History of finished sessions, stored as JSON lines in the data directory.
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+const HISTORY_FILE_NAME: &str = "history.jsonl";
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct QuestionRecord {
+    pub title: String,      // Question title
+    pub tags: Vec<String>,  // Question tags
+    pub score: f64,         // Score earned, from 0.0 to 1.0
+    pub response_time: f64, // Time taken to answer (seconds)
+    pub timed_out: bool,    // Whether the time ran out before answering
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SessionRecord {
+    pub timestamp: u64,                 // Unix time the session finished at
+    pub set_file: String,               // Path to the set file
+    pub set_title: String,              // Set title
+    pub mode: String,                   // Mode the set was run in
+    pub questions: Vec<QuestionRecord>, // Outcome of every question asked
+}
+
+/// The directory AskMe keeps its data in (`$XDG_DATA_HOME/askme` on Linux).
+pub fn data_dir() -> Result<PathBuf, AskmeError> {
+    match dirs::data_dir() {
+        Some(dir) => Ok(dir.join("askme")),
+        None => Err(AskmeError::new("Failed to find the data directory", None)),
+    }
+}
+
+pub fn history_path() -> Result<PathBuf, AskmeError> {
+    Ok(data_dir()?.join(HISTORY_FILE_NAME))
+}
+
+/// Canonical form of a set file path, so a set is recognized however it was
+/// passed on the command line.
+pub fn canonical_set_file(file_name: &str) -> String {
+    match fs::canonicalize(file_name) {
+        Ok(path) => path.to_string_lossy().to_string(),
+        Err(_) => file_name.to_string(),
+    }
+}
+
+impl SessionRecord {
+    /// Create the records of a finished session, one per set file the
+    /// questions came from.
+    pub fn from_results(set: &AskmeSet, mode: &str, results: &[QuestionResult]) -> Vec<Self> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        let mut sessions = Vec::<SessionRecord>::new();
+
+        for result in results {
+            let question = set.questions.iter().find(|qn| qn.title == result.title);
+            let set_file = question.map(|qn| qn.source.clone()).unwrap_or_default();
+
+            let record = QuestionRecord {
+                title: result.title.clone(),
+                tags: question.map(|qn| qn.tags.clone()).unwrap_or_default(),
+                score: result.score,
+                response_time: result.response_time.as_secs_f64(),
+                timed_out: result.timed_out,
+            };
+
+            match sessions.iter_mut().find(|s| s.set_file == set_file) {
+                Some(session) => session.questions.push(record),
+                None => sessions.push(SessionRecord {
+                    timestamp,
+                    set_file,
+                    set_title: set.title.clone(),
+                    mode: mode.to_string(),
+                    questions: vec![record],
+                }),
+            }
+        }
+
+        sessions
+    }
+}
+
+/// Append a session to the history file at `path`.
+pub fn append_session(path: &Path, session: &SessionRecord) -> Result<(), AskmeError> {
+    if let Some(dir) = path.parent() {
+        if let Err(e) = fs::create_dir_all(dir) {
+            return Err(AskmeError::new(
+                format!("Failed to create '{}': {}", dir.display(), e),
+                Some(Box::from(e)),
+            ));
+        }
+    }
+
+    let line = match serde_json::to_string(session) {
+        Ok(line) => line,
+        Err(e) => {
+            return Err(AskmeError::new(
+                format!("Failed to serialize session: {}", e),
+                Some(Box::from(e)),
+            ))
+        }
+    };
+
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{}", line));
+
+    match result {
+        Ok(()) => Ok(()),
+        Err(e) => Err(AskmeError::new(
+            format!("Failed to write to '{}': {}", path.display(), e),
+            Some(Box::from(e)),
+        )),
+    }
+}
+
+/// Read every session from the history file at `path`. A missing file is an
+/// empty history; lines that can't be parsed are skipped.
+pub fn load_sessions(path: &Path) -> Result<Vec<SessionRecord>, AskmeError> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(AskmeError::new(
+                format!("Failed to read '{}': {}", path.display(), e),
+                Some(Box::from(e)),
+            ))
+        }
+    };
+
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Record a finished session in the history, warning if that fails.
+pub fn record_session(set: &AskmeSet, mode: &str, results: &[QuestionResult]) {
+    let path = match history_path() {
+        Ok(path) => path,
+        Err(e) => return crate::print_warning(&format!("failed to save history: {}", e)),
+    };
+
+    for session in SessionRecord::from_results(set, mode, results) {
+        if let Err(e) = append_session(&path, &session) {
+            crate::print_warning(&format!("failed to save history: {}", e));
+        }
+    }
+}
+
+/// Format a Unix timestamp as a `YYYY-MM-DD` date (UTC).
+pub fn format_date(timestamp: u64) -> String {
+    // Days to civil date, from Howard Hinnant's date algorithms
+    let z = (timestamp / 86400) as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Accumulated outcomes of one question (or any group of answers).
+#[derive(Default, Clone, Copy)]
+pub struct AnswerStats {
+    pub attempts: usize,
+    pub total_score: f64,
+    pub total_time: f64,
+}
+
+impl AnswerStats {
+    pub fn add(&mut self, record: &QuestionRecord) {
+        self.attempts += 1;
+        self.total_score += record.score;
+        self.total_time += record.response_time;
+    }
+
+    /// Mean score, from 0.0 to 1.0.
+    pub fn accuracy(&self) -> f64 {
+        match self.attempts {
+            0 => 0.0,
+            n => self.total_score / n as f64,
+        }
+    }
+
+    pub fn average_time(&self) -> f64 {
+        match self.attempts {
+            0 => 0.0,
+            n => self.total_time / n as f64,
+        }
+    }
+}
+
+/// Outcomes of every question in `sessions`, keyed by set file and question
+/// title.
+pub fn question_stats(sessions: &[SessionRecord]) -> BTreeMap<(String, String), AnswerStats> {
+    let mut stats = BTreeMap::<(String, String), AnswerStats>::new();
+
+    for session in sessions {
+        for record in &session.questions {
+            stats
+                .entry((session.set_file.clone(), record.title.clone()))
+                .or_default()
+                .add(record);
+        }
+    }
+
+    stats
+}
+
+/// Questions of `set` worth reviewing: those answered wrong in any of the
+/// last `last_sessions` sessions of their set file, and, if `below` is given,
+/// those whose accuracy over the whole history is below it (from 0.0 to 1.0).
+pub fn weak_questions(
+    set: &AskmeSet,
+    sessions: &[SessionRecord],
+    last_sessions: usize,
+    below: Option<f64>,
+) -> Vec<Question> {
+    let stats = question_stats(sessions);
+
+    set.questions
+        .iter()
+        .filter(|question| {
+            let missed_recently = sessions
+                .iter()
+                .rev()
+                .filter(|s| s.set_file == question.source)
+                .take(last_sessions)
+                .flat_map(|s| &s.questions)
+                .any(|record| record.title == question.title && record.score < 1.0);
+
+            let below_threshold = match (
+                below,
+                stats.get(&(question.source.clone(), question.title.clone())),
+            ) {
+                (Some(below), Some(stats)) => stats.accuracy() < below,
+                _ => false,
+            };
+
+            missed_recently || below_threshold
+        })
+        .cloned()
+        .collect()
+}
+
+/// Narrow `set` down to the questions worth reviewing, according to the
+/// recorded history (see `weak_questions`). The questions left out are still
+/// in `all_questions`, where wrong options are drawn from.
+pub fn keep_weak_questions(
+    set: &mut AskmeSet,
+    last_sessions: usize,
+    below: Option<f64>,
+) -> Result<(), AskmeError> {
+    let sessions = load_sessions(&history_path()?)?;
+    set.questions = weak_questions(set, &sessions, last_sessions, below);
+    Ok(())
+}
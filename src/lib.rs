@@ -12,73 +12,214 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use colored::*;
-use inquire::{Confirm, InquireError, Text};
+use commands::Handled;
+use prelude::*;
 use rand::seq::SliceRandom;
-use std::time::Duration;
+use scoring::{GradeScale, Summary};
+use std::time::{Duration, Instant};
+use ui::{Notice, Span, Tone};
 
+pub mod cli;
+pub mod commands;
+pub mod config;
 pub mod errors;
+pub mod exam;
+pub mod generate;
+pub mod grading;
+pub mod history;
+pub mod machine;
+pub mod mastery;
 pub mod prelude;
+pub mod random;
+pub mod sampling;
+pub mod scoring;
+pub mod selector;
+pub mod session;
+pub mod timing;
 pub mod traits;
+pub mod tui;
 pub mod types;
+pub mod ui;
 
 pub fn shuffle_arr<T: Clone>(array: &[T]) -> Vec<T> {
     let mut vec = array.to_vec();
-    vec.shuffle(&mut rand::thread_rng());
+    vec.shuffle(&mut random::rng());
     vec
 }
 
 pub fn wait_for(secs: f64) {
-    std::thread::sleep(Duration::from_secs_f64(secs));
+    ui::pause(secs);
 }
 
+/// Read a non-empty answer. Returns an empty one if the user quit.
 pub fn get_input() -> String {
     loop {
-        let user_input = match Text::new("Answer:").prompt() {
-            Ok(text) => text,
-            Err(err) => {
-                if let InquireError::OperationInterrupted = err {
-                    std::process::exit(1)
-                } else {
-                    panic!("Failed to read from stdin!")
-                }
-            }
-        };
-        if !user_input.trim().is_empty() {
+        let user_input = ui::read_line("Answer:", None).unwrap_or_default();
+        if !user_input.trim().is_empty() || commands::has_quit() {
             break user_input;
         }
     }
 }
 
-pub fn print_correct_answers(correct_count: i32, questions_total: usize) {
-    println!(
-        " {}",
-        format!("Correct answers: {}/{}", correct_count, questions_total,).bright_purple()
-    )
+/// Read a line, dealing with any commands typed instead of an answer (see
+/// `commands`). Returns `None` if the time ran out or a command ended the
+/// prompt.
+fn read_answer(message: &str, deadline: Option<Instant>) -> Option<String> {
+    loop {
+        let user_input = ui::read_line(message, deadline)?;
+        match commands::handle(&user_input) {
+            Handled::Answer => break Some(user_input),
+            Handled::Again => continue,
+            Handled::Interrupt => break None,
+        }
+    }
+}
+
+/// Like `get_input`, but gives up at `deadline` if there is one, and takes
+/// commands. Returns `None` if the time ran out or a command ended the
+/// prompt.
+pub fn get_input_until(deadline: Option<Instant>) -> Option<String> {
+    loop {
+        let user_input = read_answer("Answer:", deadline)?;
+        if !user_input.trim().is_empty() {
+            break Some(user_input);
+        }
+    }
+}
+
+/// Like `get_input_until`, but an empty answer skips the question. Returns
+/// `None` if the question was skipped, the time ran out or a command ended
+/// the prompt.
+pub fn get_optional_input(deadline: Option<Instant>) -> Option<String> {
+    let user_input = read_answer("Answer (leave empty to skip):", deadline)?;
+
+    match user_input.trim().is_empty() {
+        true => None,
+        false => Some(user_input.trim().to_string()),
+    }
+}
+
+/// Block until the user presses enter, or types a command that ends the
+/// prompt.
+pub fn wait_for_enter(message: &str) {
+    read_answer(message, None);
+}
+
+/// Read the items of a list answer, giving up at `deadline` if there is one.
+///
+/// Items can be given comma-separated on one line, or one per prompt; an
+/// empty item finishes the list early.
+pub fn get_list_input(items_total: usize, deadline: Option<Instant>) -> Option<Vec<String>> {
+    let mut items = grading::split_list_answer(&get_input_until(deadline)?);
+
+    while items.len() < items_total {
+        let message = format!("Item {}/{}:", items.len() + 1, items_total);
+        let new_items = grading::split_list_answer(&read_answer(&message, deadline)?);
+        if new_items.is_empty() {
+            break;
+        }
+        items.extend(new_items);
+    }
+
+    Some(items)
+}
+
+/// Let the user pick one of `options`, returning its index, or `None` if the
+/// user quit.
+pub fn get_select_input(message: &str, options: Vec<String>) -> Option<usize> {
+    ui::select(message, &options)
+}
+
+/// Let the user pick one of `options`, shown with letters (or numbers), by
+/// moving to it or typing its label. Returns its index, or `None` if the user
+/// quit.
+pub fn get_labelled_select_input(message: &str, options: Vec<String>) -> Option<usize> {
+    ui::select_labelled(message, &options, &grading::option_labels(options.len()))
+}
+
+/// Let the user pick any number of `options`, shown with letters (or
+/// numbers), returning their indices, or `None` if the user quit.
+pub fn get_multi_select_input(message: &str, options: Vec<String>) -> Option<Vec<usize>> {
+    ui::multi_select(message, &options, &grading::option_labels(options.len()))
+}
+
+pub fn format_score(score: f64) -> String {
+    match score.fract() == 0.0 {
+        true => format!("{}", score),
+        false => format!("{:.2}", score),
+    }
+}
+
+/// Print the final score, points, percentage and grade of a session.
+pub fn print_correct_answers(summary: &Summary, grades: &GradeScale) {
+    ui::show_score(summary, grades.grade(summary.percentage()))
+}
+
+pub fn print_response_times(results: &[QuestionResult]) {
+    if results.is_empty() {
+        return;
+    }
+
+    ui::print(Tone::Bold, "Response times:");
+    for result in results {
+        let mut spans = vec![Span::plain(format!(
+            " {}: {:.1}s",
+            result.title,
+            result.response_time.as_secs_f64()
+        ))];
+        if result.timed_out {
+            spans.push(Span::new(Tone::Incorrect, " (timed out)"));
+        }
+        ui::print_spans(&spans);
+    }
+
+    let total = results.iter().map(|r| r.response_time).sum::<Duration>();
+    ui::print(
+        Tone::Bold,
+        &format!(
+            " Average: {:.1}s",
+            total.as_secs_f64() / results.len() as f64
+        ),
+    );
+    ui::print(Tone::Plain, "");
 }
 
 pub fn print_warning(msg: &str) {
-    println!("{} {}", "[!] warning:".bold(), msg.yellow());
+    ui::notify(Notice::Warning, msg);
 }
 
 pub fn print_err(msg: &str) {
-    println!("{} {}", "[!!] error:".red(), msg);
+    ui::notify(Notice::Error, msg);
 }
 
 pub fn print_info(msg: &str) {
-    println!("[i] info: {}", msg.bold())
-}
-
-pub fn get_yn_from_input() -> bool {
-    match Confirm::new("Answer:").prompt() {
-        Ok(true) => true,
-        Ok(false) => false,
-        Err(err) => {
-            if let inquire::InquireError::OperationInterrupted = err {
-                std::process::exit(1)
-            } else {
-                panic!("Failed getting user input!")
-            }
+    ui::notify(Notice::Info, msg);
+}
+
+/// Ask for a yes/no answer. Returns `None` if the user quit.
+pub fn get_yn_from_input() -> Option<bool> {
+    get_confirmation("Answer:")
+}
+
+/// Ask the user a yes/no question. Returns `None` if the user quit.
+pub fn get_confirmation(message: &str) -> Option<bool> {
+    ui::confirm(message)
+}
+
+/// Like `get_yn_from_input`, but gives up at `deadline` if there is one, and
+/// takes commands. Returns `None` if the time ran out or a command ended the
+/// prompt.
+pub fn get_yn_until(deadline: Option<Instant>) -> Option<bool> {
+    if deadline.is_none() {
+        return get_yn_from_input();
+    }
+
+    loop {
+        let input = read_answer("Answer: (y/n)", deadline)?;
+        match input.trim().to_lowercase().as_str() {
+            "y" | "yes" => break Some(true),
+            "n" | "no" => break Some(false),
+            _ => print_warning("answer with y or n"),
         }
     }
 }
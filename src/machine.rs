@@ -0,0 +1,307 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Frontend for other programs: JSON lines on stdin and stdout.
+///
+/// Everything the mode shows is written to stdout as a JSON object on a line
+/// of its own, with its kind in `type`. When the mode needs an answer, it
+/// writes a `prompt`, `select`, `multi_select` or `confirm` message and reads
+/// a line from stdin: `{"type": "answer", "answer": ...}` answers it, and
+/// `{"type": "quit"}` ends the session like `:quit` does, so the summary is
+/// still written. Closing stdin ends it the same way.
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Instant;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::commands;
+use crate::prelude::*;
+use crate::scoring::Summary;
+use crate::ui::{Notice, Span, Tone, Ui};
+
+/// Lines a program sends on stdin.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Command {
+    Answer { answer: Value },
+    Quit,
+}
+
+pub struct MachineUi {
+    input: Receiver<String>, // Lines read from stdin
+}
+
+fn emit(message: Value) {
+    let mut stdout = io::stdout().lock();
+    let _ = writeln!(stdout, "{}", message).and_then(|_| stdout.flush());
+}
+
+fn tone_name(tone: Tone) -> &'static str {
+    match tone {
+        Tone::Plain => "plain",
+        Tone::Bold => "bold",
+        Tone::Emphasis => "emphasis",
+        Tone::Title => "title",
+        Tone::Subtitle => "subtitle",
+        Tone::Highlight => "highlight",
+        Tone::Correct => "correct",
+        Tone::Partial => "partial",
+        Tone::Incorrect => "incorrect",
+    }
+}
+
+impl MachineUi {
+    pub fn new() -> Self {
+        let (sender, input) = mpsc::channel();
+
+        // Read stdin on its own thread, so timed prompts can give up waiting
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        MachineUi { input }
+    }
+
+    /// Wait for an answer, giving up at `deadline` if there is one. `parse`
+    /// turns the answer into what the prompt expects, or returns why it
+    /// can't.
+    fn read_answer<T, F>(&mut self, deadline: Option<Instant>, parse: F) -> Option<T>
+    where
+        F: Fn(&Value) -> Result<T, String>,
+    {
+        loop {
+            let line = match deadline {
+                Some(deadline) => {
+                    let timeout = deadline.saturating_duration_since(Instant::now());
+                    match self.input.recv_timeout(timeout) {
+                        Ok(line) => line,
+                        Err(RecvTimeoutError::Timeout) => {
+                            emit(json!({ "type": "timeout" }));
+                            return None;
+                        }
+                        Err(RecvTimeoutError::Disconnected) => {
+                            commands::quit();
+                            return None;
+                        }
+                    }
+                }
+                None => match self.input.recv() {
+                    Ok(line) => line,
+                    Err(_) => {
+                        commands::quit();
+                        return None;
+                    }
+                },
+            };
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let error = match serde_json::from_str::<Command>(&line) {
+                Ok(Command::Answer { answer }) => match parse(&answer) {
+                    Ok(answer) => return Some(answer),
+                    Err(e) => e,
+                },
+                Ok(Command::Quit) => {
+                    commands::quit();
+                    return None;
+                }
+                Err(e) => format!("invalid command: {}", e),
+            };
+            emit(json!({ "type": "error", "message": error }));
+        }
+    }
+}
+
+impl Default for MachineUi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ui for MachineUi {
+    fn show_title(&mut self, title: &str, subtitle: &str) {
+        emit(json!({ "type": "title", "title": title, "subtitle": subtitle }));
+    }
+
+    fn show_question(&mut self, question: &str) {
+        emit(json!({ "type": "question", "text": question }));
+    }
+
+    fn print(&mut self, spans: &[Span]) {
+        let text = spans.iter().map(|s| s.text.as_str()).collect::<String>();
+        if text.trim().is_empty() {
+            return;
+        }
+
+        // The tone of the line is the first one that stands out
+        let tone = spans
+            .iter()
+            .map(|s| s.tone)
+            .find(|&tone| tone != Tone::Plain && tone != Tone::Bold)
+            .unwrap_or(Tone::Plain);
+        emit(json!({ "type": "text", "text": text.trim(), "tone": tone_name(tone) }));
+    }
+
+    fn read_line(&mut self, message: &str, deadline: Option<Instant>) -> Option<String> {
+        let time_limit =
+            deadline.map(|d| d.saturating_duration_since(Instant::now()).as_secs_f64());
+        emit(json!({ "type": "prompt", "message": message, "time_limit": time_limit }));
+
+        self.read_answer(deadline, |answer| match answer {
+            Value::String(text) => Ok(text.clone()),
+            Value::Number(n) => Ok(n.to_string()),
+            _ => Err("expected a string answer".to_string()),
+        })
+    }
+
+    fn select(&mut self, message: &str, options: &[String]) -> usize {
+        emit(json!({ "type": "select", "message": message, "options": options }));
+
+        let parsed = self.read_answer(None, |answer| {
+            let idx = match answer {
+                Value::Number(n) => n.as_u64().map(|n| n as usize),
+                Value::String(text) => options.iter().position(|o| o == text),
+                _ => None,
+            };
+            match idx {
+                Some(idx) if idx < options.len() => Ok(idx),
+                _ => Err("expected the index of an option or the option itself".to_string()),
+            }
+        });
+        parsed.unwrap_or_default()
+    }
+
+    fn select_labelled(&mut self, message: &str, options: &[String], labels: &[String]) -> usize {
+        emit(json!({ "type": "select", "message": message, "options": options, "labels": labels }));
+
+        let parsed = self.read_answer(None, |answer| {
+            let idx = match answer {
+                Value::Number(n) => n.as_u64().map(|n| n as usize),
+                Value::String(text) => labels
+                    .iter()
+                    .position(|l| l == text)
+                    .or_else(|| options.iter().position(|o| o == text)),
+                _ => None,
+            };
+            match idx {
+                Some(idx) if idx < options.len() => Ok(idx),
+                _ => Err("expected the index, label or text of an option".to_string()),
+            }
+        });
+        parsed.unwrap_or_default()
+    }
+
+    fn multi_select(&mut self, message: &str, options: &[String], labels: &[String]) -> Vec<usize> {
+        emit(json!({
+            "type": "multi_select",
+            "message": message,
+            "options": options,
+            "labels": labels
+        }));
+
+        let parsed = self.read_answer(None, |answer| {
+            let picked = match answer {
+                Value::Array(picked) => picked,
+                _ => return Err("expected a list of options".to_string()),
+            };
+
+            let mut indices = picked
+                .iter()
+                .map(|pick| match pick {
+                    Value::Number(n) => n.as_u64().map(|n| n as usize),
+                    Value::String(text) => labels
+                        .iter()
+                        .position(|l| l == text)
+                        .or_else(|| options.iter().position(|o| o == text)),
+                    _ => None,
+                })
+                .map(|idx| idx.filter(|&idx| idx < options.len()))
+                .collect::<Option<Vec<usize>>>()
+                .ok_or("expected the indices, labels or text of options")?;
+            indices.sort();
+            indices.dedup();
+            Ok(indices)
+        });
+        parsed.unwrap_or_default()
+    }
+
+    fn confirm(&mut self, message: &str) -> bool {
+        emit(json!({ "type": "confirm", "message": message }));
+
+        let parsed = self.read_answer(None, |answer| match answer {
+            Value::Bool(yes) => Ok(*yes),
+            Value::String(text) => match text.trim().to_lowercase().as_str() {
+                "y" | "yes" => Ok(true),
+                "n" | "no" => Ok(false),
+                _ => Err("expected true, false, \"yes\" or \"no\"".to_string()),
+            },
+            _ => Err("expected true, false, \"yes\" or \"no\"".to_string()),
+        });
+        parsed.unwrap_or_default()
+    }
+
+    // Programs have no use for the delay between questions
+    fn pause(&mut self, _secs: f64) {}
+
+    fn show_progress(&mut self, done: usize, total: usize) {
+        emit(json!({ "type": "progress", "done": done, "total": total }));
+    }
+
+    fn show_result(&mut self, result: &QuestionResult) {
+        emit(json!({
+            "type": "result",
+            "title": result.title,
+            "answer": result.answer,
+            "score": result.score,
+            "points": result.points,
+            "max_points": result.max_points,
+            "response_time": result.response_time.as_secs_f64(),
+            "timed_out": result.timed_out,
+        }));
+    }
+
+    fn undo_results(&mut self, count: usize) {
+        emit(json!({ "type": "undo", "count": count }));
+    }
+
+    fn show_score(&mut self, summary: &Summary, grade: &str) {
+        emit(json!({
+            "type": "score",
+            "score": summary.correct,
+            "total": summary.asked,
+            "points": summary.points,
+            "max_points": summary.max_points,
+            "percentage": summary.percentage(),
+            "grade": grade,
+        }));
+    }
+
+    fn notify(&mut self, notice: Notice, message: &str) {
+        let kind = match notice {
+            Notice::Info => "info",
+            Notice::Warning => "warning",
+            Notice::Error => "error",
+        };
+        emit(json!({ "type": kind, "message": message }));
+    }
+}
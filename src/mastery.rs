@@ -0,0 +1,311 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Re-asking questions until they are mastered.
+use std::collections::VecDeque;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::commands;
+use crate::prelude::*;
+use crate::random::rng;
+use crate::ui::{self, Tone};
+
+/// A queue of questions that puts each question back at a random later
+/// position until it has been answered correctly `required_streak` times in a
+/// row.
+pub struct MasteryQueue {
+    questions: Vec<Question>,
+    queue: VecDeque<usize>, // Indices of the questions still to be asked
+    streaks: Vec<u32>,      // Current streak of correct answers per question
+    attempts: Vec<u32>,     // Times each question has been asked
+    recorded: Vec<(usize, u32, bool)>, // Question, old streak and whether it was put back, per answer
+    required_streak: u32,
+}
+
+impl MasteryQueue {
+    pub fn new(questions: Vec<Question>, required_streak: u32) -> Self {
+        let len = questions.len();
+
+        MasteryQueue {
+            questions,
+            queue: (0..len).collect(),
+            streaks: vec![0; len],
+            attempts: vec![0; len],
+            recorded: Vec::new(),
+            required_streak: required_streak.max(1),
+        }
+    }
+
+    /// Take the next question to ask, if any question isn't mastered yet.
+    pub fn next_question(&mut self) -> Option<(usize, Question)> {
+        let idx = self.queue.pop_front()?;
+        Some((idx, self.questions[idx].clone()))
+    }
+
+    /// Take up to `len` questions to ask together, e.g. in a matching round.
+    /// When fewer than `len` questions aren't mastered yet, mastered ones
+    /// make up the rest, so that rounds don't shrink down to a single item.
+    pub fn next_round(&mut self, len: usize) -> Vec<(usize, Question)> {
+        let mut round = Vec::new();
+        while round.len() < len {
+            match self.queue.pop_front() {
+                Some(idx) => round.push(idx),
+                None => break,
+            }
+        }
+
+        if round.is_empty() {
+            return Vec::new();
+        }
+
+        let mut mastered = (0..self.questions.len())
+            .filter(|idx| !round.contains(idx) && !self.queue.contains(idx))
+            .collect::<Vec<usize>>();
+        mastered.shuffle(&mut rng());
+        mastered.truncate(len - round.len());
+        round.extend(mastered);
+
+        round
+            .into_iter()
+            .map(|idx| (idx, self.questions[idx].clone()))
+            .collect()
+    }
+
+    /// Record the answer to a question taken with `next_question` or
+    /// `next_round`.
+    pub fn record(&mut self, idx: usize, correct: bool) {
+        let streak = self.streaks[idx];
+        self.attempts[idx] += 1;
+        self.streaks[idx] = match correct {
+            true => streak + 1,
+            false => 0,
+        };
+
+        let requeued = self.streaks[idx] < self.required_streak;
+        if requeued {
+            let position = match self.queue.len() {
+                0 => 0,
+                len => rng().gen_range(1..=len),
+            };
+            self.queue.insert(position, idx);
+        }
+        self.recorded.push((idx, streak, requeued));
+    }
+
+    /// Take back the last answer recorded, after `:undo`. Unless it was
+    /// already mastered, its question is asked again next.
+    pub fn undo(&mut self) {
+        let (idx, streak, requeued) = match self.recorded.pop() {
+            Some(recorded) => recorded,
+            None => return,
+        };
+        self.attempts[idx] -= 1;
+        self.streaks[idx] = streak;
+
+        if requeued {
+            self.queue.retain(|&queued| queued != idx);
+        }
+        if streak < self.required_streak {
+            self.queue.push_front(idx);
+        }
+    }
+
+    pub fn mastered_count(&self) -> usize {
+        self.streaks
+            .iter()
+            .filter(|&&streak| streak >= self.required_streak)
+            .count()
+    }
+
+    pub fn print_progress(&self) {
+        ui::print(
+            Tone::Highlight,
+            &format!(
+                " {}/{} mastered",
+                self.mastered_count(),
+                self.questions.len()
+            ),
+        );
+        ui::print(Tone::Plain, "");
+    }
+
+    pub fn print_summary(&self) {
+        ui::print(Tone::Bold, "Attempts needed per card:");
+
+        for (question, attempts) in self.questions.iter().zip(&self.attempts) {
+            ui::print(Tone::Plain, &format!(" {}: {}", question.title, attempts));
+        }
+        ui::print(Tone::Plain, "");
+    }
+}
+
+/// Ask `questions` with `ask` until each has been answered correctly
+/// `required_streak` times in a row, then print how many attempts each took.
+///
+/// `ask` returns whether the answer was correct, or `None` to stop early.
+pub fn run_until_mastered<F>(questions: Vec<Question>, required_streak: u32, mut ask: F)
+where
+    F: FnMut(&Question) -> Option<bool>,
+{
+    run_rounds_until_mastered(questions, required_streak, 1, |round| {
+        ask(&round[0]).map(|correct| vec![correct])
+    })
+}
+
+/// Like `run_until_mastered`, but asks up to `round_len` questions at a time
+/// (see `MasteryQueue::next_round`). `ask` returns whether each answer of the
+/// round was correct, or `None` to stop early.
+pub fn run_rounds_until_mastered<F>(
+    questions: Vec<Question>,
+    required_streak: u32,
+    round_len: usize,
+    mut ask: F,
+) where
+    F: FnMut(&[Question]) -> Option<Vec<bool>>,
+{
+    let mut queue = MasteryQueue::new(questions, required_streak);
+    let mut rounds = Vec::new(); // Number of answers recorded in each round
+
+    loop {
+        let round = queue.next_round(round_len.max(1));
+        if round.is_empty() {
+            break;
+        }
+        ui::show_progress(queue.mastered_count(), queue.questions.len());
+
+        let (indices, questions): (Vec<usize>, Vec<Question>) = round.into_iter().unzip();
+        let correct = match ask(&questions) {
+            Some(correct) => correct,
+            None => break,
+        };
+
+        // Rounds taken back with `:undo` while asking this one no longer count
+        for _ in 0..commands::take_undone() {
+            for _ in 0..rounds.pop().unwrap_or_default() {
+                queue.undo();
+            }
+        }
+        rounds.push(correct.len().min(indices.len()));
+        for (idx, correct) in indices.into_iter().zip(correct) {
+            queue.record(idx, correct);
+        }
+        queue.print_progress();
+    }
+
+    queue.print_summary();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queue(len: usize, required_streak: u32) -> MasteryQueue {
+        let questions = (0..len)
+            .map(|n| format!("- title: Q{}\n  answers: [A{}]\n", n, n))
+            .collect::<String>();
+        MasteryQueue::new(serde_yaml::from_str(&questions).unwrap(), required_streak)
+    }
+
+    /// Answer every question with `correct` until the queue runs out (or
+    /// `limit` answers were given), returning the questions in the order
+    /// they were asked.
+    fn answer_all(
+        queue: &mut MasteryQueue,
+        limit: usize,
+        correct: impl Fn(usize) -> bool,
+    ) -> Vec<usize> {
+        let mut asked = Vec::new();
+        while let Some((idx, _)) = queue.next_question() {
+            asked.push(idx);
+            queue.record(idx, correct(idx));
+            if asked.len() == limit {
+                break;
+            }
+        }
+        asked
+    }
+
+    #[test]
+    fn questions_come_back_until_their_streak_is_reached() {
+        let mut queue = queue(3, 2);
+
+        let asked = answer_all(&mut queue, 100, |_| true);
+        assert_eq!(asked.len(), 6);
+        assert_eq!(queue.attempts, vec![2, 2, 2]);
+        assert_eq!(queue.mastered_count(), 3);
+        assert!(queue.next_question().is_none());
+    }
+
+    #[test]
+    fn a_wrong_answer_starts_the_streak_over() {
+        let mut queue = queue(1, 2);
+
+        assert_eq!(answer_all(&mut queue, 2, |_| true), vec![0, 0]);
+        assert_eq!(queue.mastered_count(), 1);
+        queue.undo();
+        assert_eq!(answer_all(&mut queue, 1, |_| false), vec![0]);
+        assert_eq!(queue.streaks[0], 0);
+
+        assert_eq!(answer_all(&mut queue, 100, |_| true), vec![0, 0]);
+        assert_eq!(queue.attempts[0], 4);
+    }
+
+    #[test]
+    fn only_missed_questions_are_asked_again() {
+        let mut queue = queue(3, 1);
+
+        let asked = answer_all(&mut queue, 3, |idx| idx != 1);
+        assert_eq!(asked, vec![0, 1, 2]);
+        assert_eq!(queue.mastered_count(), 2);
+        assert_eq!(answer_all(&mut queue, 100, |_| true), vec![1]);
+    }
+
+    #[test]
+    fn undo_asks_the_question_again_next() {
+        let mut queue = queue(3, 1);
+
+        let (idx, _) = queue.next_question().unwrap();
+        queue.record(idx, false);
+        queue.undo();
+        assert_eq!(queue.attempts[idx], 0);
+        assert_eq!(queue.queue, [idx, 1, 2]);
+
+        // Mastering it is taken back too
+        let (idx, _) = queue.next_question().unwrap();
+        queue.record(idx, true);
+        assert_eq!(queue.mastered_count(), 1);
+        queue.undo();
+        assert_eq!(queue.mastered_count(), 0);
+        assert_eq!(queue.next_question().unwrap().0, idx);
+    }
+
+    #[test]
+    fn rounds_are_made_up_with_mastered_questions() {
+        let mut queue = queue(3, 1);
+
+        for (idx, _) in queue.next_round(2) {
+            queue.record(idx, true);
+        }
+        let round = queue.next_round(3);
+        let mut indices = round.iter().map(|(idx, _)| *idx).collect::<Vec<usize>>();
+        assert_eq!(indices[0], 2);
+        indices.sort();
+        assert_eq!(indices, vec![0, 1, 2]);
+
+        queue.record(2, true);
+        assert!(queue.next_round(3).is_empty());
+    }
+}
@@ -0,0 +1,195 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Drawing a random subset of a set's questions.
+use rand::seq::SliceRandom;
+
+use crate::prelude::*;
+use crate::print_warning;
+use crate::random::rng;
+
+/// How many questions to draw for each tag, as given by `tag=N,...`.
+#[derive(Clone, Debug)]
+pub struct TagCounts(pub Vec<(String, usize)>);
+
+/// Parse `tag=N,...` into tag counts (usable as a clap value parser).
+pub fn parse_tag_counts(input: &str) -> Result<TagCounts, String> {
+    input
+        .split(',')
+        .map(|pair| match pair.split_once('=') {
+            Some((tag, count)) if !tag.trim().is_empty() => match count.trim().parse() {
+                Ok(count) => Ok((tag.trim().to_string(), count)),
+                Err(_) => Err(format!("invalid count in '{}'", pair)),
+            },
+            _ => Err(format!("expected TAG=N, found '{}'", pair)),
+        })
+        .collect::<Result<Vec<(String, usize)>, String>>()
+        .map(TagCounts)
+}
+
+/// Draw a random subset of `questions`, keeping their order. Callers only
+/// narrow down the questions asked with it, not `AskmeSet::all_questions`.
+///
+/// The questions of each tag in `by_tag` are drawn first; `count` then tops
+/// the subset up with any other questions, up to `count` questions in total.
+pub fn sample_questions(
+    questions: &[Question],
+    count: Option<usize>,
+    by_tag: Option<&TagCounts>,
+) -> Vec<Question> {
+    let mut rng = rng();
+    let mut picked = vec![false; questions.len()];
+
+    for (tag, tag_count) in by_tag.map(|t| t.0.as_slice()).unwrap_or_default() {
+        let candidates = (0..questions.len())
+            .filter(|&i| !picked[i] && questions[i].tags.contains(tag))
+            .collect::<Vec<usize>>();
+
+        if candidates.len() < *tag_count {
+            print_warning(&format!(
+                "only {} questions left tagged '{}'",
+                candidates.len(),
+                tag
+            ));
+        }
+
+        for &i in candidates.choose_multiple(&mut rng, *tag_count) {
+            picked[i] = true;
+        }
+    }
+
+    let picked_count = picked.iter().filter(|&&p| p).count();
+    match count {
+        Some(count) if count > picked_count => {
+            let rest = (0..questions.len())
+                .filter(|&i| !picked[i])
+                .collect::<Vec<usize>>();
+
+            for &i in rest.choose_multiple(&mut rng, count - picked_count) {
+                picked[i] = true;
+            }
+        }
+        Some(_) => (),
+        // Without a count only the tagged questions are asked
+        None if by_tag.is_some() => (),
+        None => return questions.to_vec(),
+    }
+
+    questions
+        .iter()
+        .zip(picked)
+        .filter(|(_, picked)| *picked)
+        .map(|(question, _)| question.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::seed_rng;
+
+    /// Questions Q0 to Q5: the even ones tagged "even", Q0 and Q1 tagged
+    /// "low".
+    fn questions() -> Vec<Question> {
+        let yaml = (0..6)
+            .map(|n| {
+                let mut tags = Vec::new();
+                if n % 2 == 0 {
+                    tags.push("even");
+                }
+                if n < 2 {
+                    tags.push("low");
+                }
+                format!(
+                    "- {{ title: Q{}, answers: [A{}], tags: [{}] }}\n",
+                    n,
+                    n,
+                    tags.join(", ")
+                )
+            })
+            .collect::<String>();
+        serde_yaml::from_str(&yaml).unwrap()
+    }
+
+    fn titles(questions: &[Question]) -> Vec<&str> {
+        questions.iter().map(|qn| qn.title.as_str()).collect()
+    }
+
+    fn tag_counts(input: &str) -> TagCounts {
+        parse_tag_counts(input).unwrap()
+    }
+
+    #[test]
+    fn tag_counts_are_parsed() {
+        assert_eq!(
+            tag_counts("even=2, low = 1").0,
+            vec![("even".to_string(), 2), ("low".to_string(), 1)]
+        );
+
+        for input in [
+            "even", "=2", " =2", "even=", "even=x", "even=-1", "even=2,", "",
+        ] {
+            assert!(parse_tag_counts(input).is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn without_limits_every_question_is_asked() {
+        assert_eq!(titles(&sample_questions(&questions(), None, None)).len(), 6);
+    }
+
+    #[test]
+    fn count_draws_that_many_in_order() {
+        seed_rng(1);
+        let questions = questions();
+        let sample = sample_questions(&questions, Some(3), None);
+
+        assert_eq!(sample.len(), 3);
+        let positions = titles(&sample)
+            .iter()
+            .map(|title| questions.iter().position(|qn| qn.title == *title).unwrap())
+            .collect::<Vec<usize>>();
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+
+        assert_eq!(sample_questions(&questions, Some(10), None).len(), 6);
+    }
+
+    #[test]
+    fn tags_are_drawn_first_and_topped_up_to_count() {
+        seed_rng(2);
+        let questions = questions();
+
+        let sample = sample_questions(&questions, None, Some(&tag_counts("even=2")));
+        assert_eq!(sample.len(), 2);
+        assert!(sample
+            .iter()
+            .all(|qn| qn.tags.contains(&"even".to_string())));
+
+        // Every even question is drawn first, which leaves Q1 as the only
+        // "low" one
+        let sample = sample_questions(&questions, None, Some(&tag_counts("even=3,low=1")));
+        assert_eq!(titles(&sample), ["Q0", "Q1", "Q2", "Q4"]);
+
+        let sample = sample_questions(&questions, Some(4), Some(&tag_counts("low=2")));
+        assert_eq!(sample.len(), 4);
+        assert_eq!(titles(&sample)[..2], ["Q0", "Q1"]);
+    }
+
+    #[test]
+    fn tags_with_too_few_questions_give_what_there_is() {
+        let questions = questions();
+        let sample = sample_questions(&questions, None, Some(&tag_counts("low=5,none=1")));
+        assert_eq!(titles(&sample), ["Q0", "Q1"]);
+    }
+}
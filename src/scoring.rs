@@ -0,0 +1,248 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Points, partial credit and grades.
+///
+/// Every answer earns credit from 0.0 to 1.0 (the `score` of its result),
+/// which is worth that share of the question's `points`. A wrong multiple
+/// choice answer can cost points instead, and the session's percentage of
+/// the points possible is turned into a grade.
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::config::Options;
+use crate::prelude::*;
+
+/// Grades and the percentage each needs, highest first. Below every
+/// boundary, the lowest grade is given.
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+#[serde(from = "BTreeMap<String, f64>")]
+pub struct GradeScale(Vec<(String, f64)>);
+
+impl From<BTreeMap<String, f64>> for GradeScale {
+    fn from(boundaries: BTreeMap<String, f64>) -> Self {
+        GradeScale::new(boundaries.into_iter().collect())
+    }
+}
+
+impl Default for GradeScale {
+    fn default() -> Self {
+        let boundaries = [
+            ("A", 90.0),
+            ("B", 80.0),
+            ("C", 70.0),
+            ("D", 60.0),
+            ("F", 0.0),
+        ];
+        GradeScale::new(
+            boundaries
+                .iter()
+                .map(|&(grade, min)| (grade.to_string(), min))
+                .collect(),
+        )
+    }
+}
+
+impl GradeScale {
+    fn new(mut boundaries: Vec<(String, f64)>) -> Self {
+        boundaries.sort_by(|a, b| b.1.total_cmp(&a.1));
+        GradeScale(boundaries)
+    }
+
+    pub fn boundaries(&self) -> &[(String, f64)] {
+        &self.0
+    }
+
+    /// The grade for `percentage`.
+    pub fn grade(&self, percentage: f64) -> &str {
+        self.0
+            .iter()
+            .find(|(_, min)| percentage >= *min)
+            .or(self.0.last())
+            .map(|(grade, _)| grade.as_str())
+            .unwrap_or_default()
+    }
+}
+
+/// Parse grade boundaries such as "A=90,B=75,C=50,F=0".
+pub fn parse_grades(input: &str) -> Result<GradeScale, String> {
+    input
+        .split(',')
+        .map(|pair| match pair.split_once('=') {
+            Some((grade, min)) if !grade.trim().is_empty() => match min.trim().parse() {
+                Ok(min) => Ok((grade.trim().to_string(), min)),
+                Err(_) => Err(format!("invalid percentage in '{}'", pair)),
+            },
+            _ => Err(format!("expected GRADE=PERCENT, found '{}'", pair)),
+        })
+        .collect::<Result<Vec<(String, f64)>, String>>()
+        .map(GradeScale::new)
+}
+
+/// How answers are turned into points. By default wrong answers cost
+/// nothing, hints are free and typos earn no credit.
+#[derive(Clone, Default, Debug)]
+pub struct Scoring {
+    pub negative_marking: f64, // Share of a question's points lost for a wrong choice
+    pub hint_penalty: f64,     // Share of the credit lost for each hint taken
+    pub typo_credit: f64,      // Credit for a typed answer that is only off by a typo
+}
+
+impl From<&Options> for Scoring {
+    fn from(options: &Options) -> Self {
+        Scoring {
+            negative_marking: options.negative_marking.unwrap_or_default(),
+            hint_penalty: options.hint_penalty.unwrap_or_default(),
+            typo_credit: options.typo_credit.unwrap_or_default(),
+        }
+    }
+}
+
+impl Scoring {
+    /// What is left of `credit` after taking `hints` hints.
+    pub fn after_hints(&self, credit: f64, hints: usize) -> f64 {
+        credit * (1.0 - self.hint_penalty * hints as f64).max(0.0)
+    }
+
+    /// Points earned on `question` for `credit`. A wrong choice (as opposed
+    /// to no answer at all) may cost points instead.
+    pub fn points(&self, question: &Question, credit: f64, wrong_choice: bool) -> f64 {
+        match wrong_choice {
+            // Subtracted from 0 so that no penalty is 0 rather than -0
+            true => 0.0 - self.negative_marking * question.points,
+            false => credit * question.points,
+        }
+    }
+}
+
+/// Totals of a session.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Summary {
+    pub correct: f64,    // Credit earned, i.e. the number of correct answers
+    pub asked: usize,    // Questions asked
+    pub points: f64,     // Points earned (negative marking can take it below zero)
+    pub max_points: f64, // Points that could have been earned
+}
+
+impl Summary {
+    pub fn from_results(results: &[QuestionResult]) -> Self {
+        results
+            .iter()
+            .fold(Summary::default(), |summary, result| Summary {
+                correct: summary.correct + result.score,
+                asked: summary.asked + 1,
+                points: summary.points + result.points,
+                max_points: summary.max_points + result.max_points,
+            })
+    }
+
+    /// Share of the points possible that was earned, from 0 to 100.
+    pub fn percentage(&self) -> f64 {
+        match self.max_points > 0.0 {
+            true => (self.points / self.max_points * 100.0).max(0.0),
+            false => 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn question(points: f64) -> Question {
+        let mut question: Question =
+            serde_yaml::from_str("{title: H, answers: [Hydrogen]}").unwrap();
+        question.points = points;
+        question
+    }
+
+    #[test]
+    fn grades_are_parsed() {
+        let scale = parse_grades(" B = 75 ,A=90,F=0").unwrap();
+        assert_eq!(
+            scale.boundaries(),
+            [
+                ("A".to_string(), 90.0),
+                ("B".to_string(), 75.0),
+                ("F".to_string(), 0.0)
+            ]
+        );
+        assert_eq!(parse_grades("pass=50.5").unwrap().grade(50.5), "pass");
+
+        for input in ["", "A", "A=", "A=x", "=90", " =90", "A=90,", "A=90,,F=0"] {
+            assert!(parse_grades(input).is_err(), "{:?} should not parse", input);
+        }
+    }
+
+    #[test]
+    fn boundaries_belong_to_the_grade_above() {
+        let scale = GradeScale::default();
+        assert_eq!(scale.grade(100.0), "A");
+        assert_eq!(scale.grade(90.0), "A");
+        assert_eq!(scale.grade(89.99), "B");
+        assert_eq!(scale.grade(60.0), "D");
+        assert_eq!(scale.grade(59.9), "F");
+        assert_eq!(scale.grade(0.0), "F");
+
+        // Below every boundary, the lowest grade is given
+        let scale = parse_grades("A=90,C=50").unwrap();
+        assert_eq!(scale.grade(49.0), "C");
+        assert_eq!(scale.grade(-10.0), "C");
+    }
+
+    #[test]
+    fn wrong_choices_cost_their_share_of_the_points() {
+        let scoring = Scoring {
+            negative_marking: 0.25,
+            ..Scoring::default()
+        };
+        let question = question(4.0);
+
+        assert_eq!(scoring.points(&question, 1.0, false), 4.0);
+        assert_eq!(scoring.points(&question, 0.5, false), 2.0);
+        assert_eq!(scoring.points(&question, 0.0, false), 0.0);
+        assert_eq!(scoring.points(&question, 0.0, true), -1.0);
+    }
+
+    #[test]
+    fn wrong_choices_without_negative_marking_earn_zero_not_minus_zero() {
+        let points = Scoring::default().points(&question(2.0), 0.0, true);
+        assert_eq!(points, 0.0);
+        assert!(points.is_sign_positive());
+        assert_eq!(format!("{}", points), "0");
+    }
+
+    #[test]
+    fn hints_take_their_share_of_the_credit() {
+        let scoring = Scoring {
+            hint_penalty: 0.4,
+            ..Scoring::default()
+        };
+        assert_eq!(scoring.after_hints(1.0, 0), 1.0);
+        assert!((scoring.after_hints(1.0, 1) - 0.6).abs() < 1e-9);
+        assert_eq!(scoring.after_hints(1.0, 3), 0.0);
+    }
+
+    #[test]
+    fn the_percentage_does_not_go_below_zero() {
+        let summary = Summary {
+            points: -2.0,
+            max_points: 4.0,
+            ..Summary::default()
+        };
+        assert_eq!(summary.percentage(), 0.0);
+        assert_eq!(Summary::default().percentage(), 0.0);
+    }
+}
@@ -0,0 +1,318 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Adaptive question selection, favouring heavy and often missed questions.
+use std::collections::VecDeque;
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+
+use crate::commands;
+use crate::history::{history_path, load_sessions, SessionRecord};
+use crate::prelude::*;
+use crate::print_warning;
+use crate::random::rng;
+use crate::ui;
+
+/// How many of the latest answers to a question make up its error rate.
+const RECENT_ANSWERS: usize = 5;
+/// Error rate assumed for questions that were never answered.
+const UNSEEN_ERROR_RATE: f64 = 0.5;
+/// Share of its weight a question keeps when it is never missed, so that
+/// known questions still come up once in a while.
+const MIN_WEIGHT_FACTOR: f64 = 0.2;
+
+/// Samples questions with a probability proportional to their `weight` and
+/// their recent error rate.
+pub struct AdaptiveSelector {
+    questions: Vec<Question>,
+    recent: Vec<VecDeque<f64>>, // Latest scores of each question, oldest first
+    last: Option<usize>,        // Last question asked, not to be repeated right away
+    recorded: Vec<(usize, Option<f64>)>, // Question and the score it pushed out, per score
+}
+
+impl AdaptiveSelector {
+    /// Create a selector, taking the error rates from the recorded `sessions`
+    /// followed by the `results` of the current session.
+    pub fn new(
+        questions: Vec<Question>,
+        sessions: &[SessionRecord],
+        results: &[QuestionResult],
+    ) -> Self {
+        let mut selector = AdaptiveSelector {
+            recent: vec![VecDeque::new(); questions.len()],
+            questions,
+            last: None,
+            recorded: Vec::new(),
+        };
+
+        for session in sessions {
+            for record in &session.questions {
+                let idx = selector
+                    .questions
+                    .iter()
+                    .position(|qn| qn.source == session.set_file && qn.title == record.title);
+                if let Some(idx) = idx {
+                    selector.record(idx, record.score);
+                }
+            }
+        }
+
+        for result in results {
+            if let Some(idx) = selector
+                .questions
+                .iter()
+                .position(|qn| qn.title == result.title)
+            {
+                selector.record(idx, result.score);
+            }
+        }
+
+        selector.last = None;
+        selector.recorded.clear();
+        selector
+    }
+
+    /// Like `new`, with the sessions read from the history file.
+    pub fn from_history(questions: Vec<Question>, results: &[QuestionResult]) -> Self {
+        let sessions = match history_path().and_then(|path| load_sessions(&path)) {
+            Ok(sessions) => sessions,
+            Err(e) => {
+                print_warning(&format!("failed to read history: {}", e));
+                Vec::new()
+            }
+        };
+
+        AdaptiveSelector::new(questions, &sessions, results)
+    }
+
+    pub fn error_rate(&self, idx: usize) -> f64 {
+        let recent = &self.recent[idx];
+
+        match recent.len() {
+            0 => UNSEEN_ERROR_RATE,
+            len => 1.0 - recent.iter().sum::<f64>() / len as f64,
+        }
+    }
+
+    pub fn weight(&self, idx: usize) -> f64 {
+        self.questions[idx].weight.max(0.0) * (MIN_WEIGHT_FACTOR + self.error_rate(idx))
+    }
+
+    /// Sample the next question to ask.
+    pub fn next_question<R: Rng>(&mut self, rng: &mut R) -> Option<(usize, Question)> {
+        let mut weights = (0..self.questions.len())
+            .map(|idx| self.weight(idx))
+            .collect::<Vec<f64>>();
+
+        if let Some(last) = self.last {
+            if weights.len() > 1 {
+                weights[last] = 0.0;
+            }
+        }
+
+        let idx = match WeightedIndex::new(&weights) {
+            Ok(dist) => dist.sample(rng),
+            // Every weight is zero, so fall back to picking uniformly
+            Err(_) if !self.questions.is_empty() => rng.gen_range(0..self.questions.len()),
+            Err(_) => return None,
+        };
+
+        self.last = Some(idx);
+        Some((idx, self.questions[idx].clone()))
+    }
+
+    /// Record the score of a question taken with `next_question`.
+    pub fn record(&mut self, idx: usize, score: f64) {
+        let recent = &mut self.recent[idx];
+
+        recent.push_back(score);
+        let dropped = match recent.len() > RECENT_ANSWERS {
+            true => recent.pop_front(),
+            false => None,
+        };
+        self.last = Some(idx);
+        self.recorded.push((idx, dropped));
+    }
+
+    /// Take back the last score recorded, after `:undo`. Returns whether
+    /// there was one.
+    pub fn undo(&mut self) -> bool {
+        let (idx, dropped) = match self.recorded.pop() {
+            Some(recorded) => recorded,
+            None => return false,
+        };
+
+        let recent = &mut self.recent[idx];
+        recent.pop_back();
+        if let Some(score) = dropped {
+            recent.push_front(score);
+        }
+        true
+    }
+
+    /// Ask `count` sampled questions with `ask`, which returns the score
+    /// earned, or `None` to stop early. Answers taken back with `:undo` don't
+    /// count towards `count`.
+    pub fn run<F>(&mut self, count: usize, mut ask: F)
+    where
+        F: FnMut(&Question) -> Option<f64>,
+    {
+        let mut rng = rng();
+        let mut asked = 0;
+
+        while asked < count {
+            ui::show_progress(asked, count);
+
+            let (idx, question) = match self.next_question(&mut rng) {
+                Some(next) => next,
+                None => break,
+            };
+
+            let score = match ask(&question) {
+                Some(score) => score,
+                None => break,
+            };
+            for _ in 0..commands::take_undone() {
+                if self.undo() {
+                    asked -= 1;
+                }
+            }
+            self.record(idx, score);
+            asked += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use crate::history::QuestionRecord;
+    use crate::random::seed_rng;
+
+    /// Questions Q0, Q1, ... with the given weights.
+    fn questions(weights: &[f64]) -> Vec<Question> {
+        let yaml = weights
+            .iter()
+            .enumerate()
+            .map(|(n, weight)| {
+                format!(
+                    "- {{ title: Q{}, answers: [A{}], weight: {} }}\n",
+                    n, n, weight
+                )
+            })
+            .collect::<String>();
+        serde_yaml::from_str(&yaml).unwrap()
+    }
+
+    /// How many times each question is picked out of `draws`.
+    fn picks(selector: &mut AdaptiveSelector, draws: usize) -> Vec<usize> {
+        seed_rng(7);
+        let mut picks = vec![0; selector.questions.len()];
+        for _ in 0..draws {
+            let (idx, _) = selector.next_question(&mut rng()).unwrap();
+            picks[idx] += 1;
+        }
+        picks
+    }
+
+    #[test]
+    fn error_rates_come_from_the_latest_answers() {
+        let mut selector = AdaptiveSelector::new(questions(&[1.0, 1.0]), &[], &[]);
+        assert_eq!(selector.error_rate(0), UNSEEN_ERROR_RATE);
+
+        selector.record(0, 0.0);
+        selector.record(0, 0.5);
+        assert_eq!(selector.error_rate(0), 0.75);
+
+        // Only the last RECENT_ANSWERS count
+        for _ in 0..RECENT_ANSWERS {
+            selector.record(0, 1.0);
+        }
+        assert_eq!(selector.error_rate(0), 0.0);
+        assert_eq!(selector.weight(0), MIN_WEIGHT_FACTOR);
+
+        // Undoing brings back the answer pushed out
+        assert!(selector.undo());
+        let expected = 0.5 / RECENT_ANSWERS as f64;
+        assert!((selector.error_rate(0) - expected).abs() < 1e-9);
+        selector.record(1, 1.0);
+        assert!(selector.undo());
+        assert_eq!(selector.error_rate(1), UNSEEN_ERROR_RATE);
+    }
+
+    #[test]
+    fn missed_and_heavy_questions_come_up_more_often() {
+        // Q0 was missed, Q1 answered right; Q2 is unseen but heavy
+        let results = [("Q0", 0.0), ("Q1", 1.0)].map(|(title, score)| QuestionResult {
+            title: title.to_string(),
+            answer: None,
+            score,
+            points: score,
+            max_points: 1.0,
+            response_time: Duration::ZERO,
+            timed_out: false,
+        });
+        let mut selector = AdaptiveSelector::new(questions(&[1.0, 1.0, 4.0]), &[], &results);
+
+        assert!(selector.weight(0) > selector.weight(1));
+        assert!(selector.weight(2) > selector.weight(0));
+
+        let picks = picks(&mut selector, 2000);
+        assert!(picks[0] > 2 * picks[1], "{:?}", picks);
+        assert!(picks[2] > picks[0], "{:?}", picks);
+    }
+
+    #[test]
+    fn the_history_counts_questions_of_the_same_set_only() {
+        let mut questions = questions(&[1.0, 1.0]);
+        for question in &mut questions {
+            question.source = "elements.yaml".to_string();
+        }
+        let session = |set_file: &str, score: f64| SessionRecord {
+            timestamp: 0,
+            set_file: set_file.to_string(),
+            set_title: "Elements".to_string(),
+            mode: "memorize".to_string(),
+            questions: vec![QuestionRecord {
+                title: "Q0".to_string(),
+                tags: Vec::new(),
+                score,
+                response_time: 1.0,
+                timed_out: false,
+            }],
+        };
+        let sessions = [session("elements.yaml", 1.0), session("other.yaml", 0.0)];
+
+        let selector = AdaptiveSelector::new(questions, &sessions, &[]);
+        assert_eq!(selector.error_rate(0), 0.0);
+        assert_eq!(selector.error_rate(1), UNSEEN_ERROR_RATE);
+    }
+
+    #[test]
+    fn the_same_question_is_not_asked_twice_in_a_row() {
+        let mut selector = AdaptiveSelector::new(questions(&[1.0, 0.0, 1.0]), &[], &[]);
+        seed_rng(3);
+
+        let mut last = None;
+        for _ in 0..100 {
+            let (idx, _) = selector.next_question(&mut rng()).unwrap();
+            assert_ne!(idx, 1, "a question of no weight came up");
+            assert_ne!(Some(idx), last);
+            last = Some(idx);
+        }
+    }
+}
@@ -0,0 +1,699 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Quiz sessions driven by the caller, for embedding AskMe.
+///
+/// A `Session` never prints or reads anything: the caller asks it for the
+/// current `Prompt`, shows it however it likes, and hands the user's
+/// `Answer` back, getting `Feedback` in return, until `prompt` returns
+/// `None`.
+use std::time::Instant;
+
+use crate::generate::{
+    choices_available, multiple_choices, multiple_select_choices, true_false_statement, Distractors,
+};
+use crate::grading::{
+    grade_answer, grade_list, grade_order, grade_selection, option_labels, parse_option,
+    parse_options, parse_order, split_list_answer, OrderScoring, SelectScoring,
+};
+use crate::prelude::*;
+use crate::scoring::Scoring;
+use crate::shuffle_arr;
+
+/// How the questions are put to the user, after the modes of the same name.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SessionMode {
+    Memorize,    // Type the answers
+    Multichoice, // Pick the answer from several choices
+    Correctme,   // Say whether a statement is true
+}
+
+impl SessionMode {
+    /// Name of the mode, as recorded in the history.
+    pub fn name(&self) -> &'static str {
+        match self {
+            SessionMode::Memorize => "memorize",
+            SessionMode::Multichoice => "multichoice",
+            SessionMode::Correctme => "correctme",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SessionSettings {
+    pub shuffle: bool,                 // Whether to shuffle the questions
+    pub case_sensitive: bool,          // Whether answers are case-sensitive
+    pub max_choices: usize,            // Choices for each question (multichoice)
+    pub distractors: Distractors,      // How the wrong choices are picked (multichoice)
+    pub select_all: bool,              // Whether multiple choice asks for every correct choice
+    pub select_scoring: SelectScoring, // How "select all that apply" questions are scored
+    pub order_scoring: OrderScoring,   // How ordering questions are scored
+    pub scoring: Scoring,              // How answers are turned into points
+}
+
+impl Default for SessionSettings {
+    fn default() -> Self {
+        SessionSettings {
+            shuffle: false,
+            case_sensitive: false,
+            max_choices: 4,
+            distractors: Distractors::Random,
+            select_all: false,
+            select_scoring: SelectScoring::Partial,
+            order_scoring: OrderScoring::Position,
+            scoring: Scoring::default(),
+        }
+    }
+}
+
+/// What the user is asked to do.
+#[derive(Clone, PartialEq, Debug)]
+pub enum PromptKind {
+    Text,                               // Type the answer: `Answer::Text`
+    List { items: usize },              // Give every item of the answer: `Answer::Items`
+    Order { items: Vec<String> },       // Put the items in order: `Answer::Order`
+    Choice { choices: Vec<String> },    // Pick one of the choices: `Answer::Choice`
+    Selection { choices: Vec<String> }, // Pick every correct choice: `Answer::Choices`
+    Statement { answer: String }, // Say whether "question is answer" is true: `Answer::TrueFalse`
+}
+
+/// A question put to the user.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Prompt {
+    pub number: usize, // From 1 to `total`
+    pub total: usize,
+    pub question: String,
+    pub kind: PromptKind,
+}
+
+/// The user's answer to a prompt.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Answer {
+    Text(String),
+    Items(Vec<String>),
+    Order(Vec<usize>), // Indices of the prompt's items, in the order the user put them
+    Choice(usize),     // Index of the picked choice
+    Choices(Vec<usize>), // Indices of the picked choices
+    TrueFalse(bool),
+}
+
+impl Answer {
+    /// Read a typed answer to `kind` the way the terminal modes do: list
+    /// items are comma-separated, orders are given as letters, choices by
+    /// their label, number or text (see `grading::parse_option`) and
+    /// statements as yes or no.
+    pub fn parse(kind: &PromptKind, input: &str) -> Option<Answer> {
+        let input = input.trim();
+
+        match kind {
+            PromptKind::Text => Some(Answer::Text(input.to_string())),
+            PromptKind::List { .. } => Some(Answer::Items(split_list_answer(input))),
+            PromptKind::Order { items } => parse_order(input, items.len()).map(Answer::Order),
+            PromptKind::Choice { choices } => parse_option(input, choices).map(Answer::Choice),
+            PromptKind::Selection { choices } => {
+                parse_options(input, choices.len()).map(Answer::Choices)
+            }
+            PromptKind::Statement { .. } => match input.to_lowercase().as_str() {
+                "y" | "yes" | "true" => Some(Answer::TrueFalse(true)),
+                "n" | "no" | "false" => Some(Answer::TrueFalse(false)),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// The outcome of an answer.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Feedback {
+    pub score: f64,           // From 0.0 to 1.0
+    pub points: f64,          // Points earned, negative for a penalized wrong choice
+    pub correct: Vec<String>, // The correct answer(s), in order for ordering questions
+    pub missed: Vec<String>,  // Items of a list answer the user missed
+    pub extra: Vec<String>,   // Items the user gave that are not part of the answer
+}
+
+impl Feedback {
+    pub fn is_correct(&self) -> bool {
+        self.score >= 1.0
+    }
+}
+
+/// The prompt being answered, along with what grading it takes.
+struct Pending {
+    prompt: Prompt,
+    shown: Vec<usize>,   // Correct position of each item shown (ordering questions)
+    correct: Vec<usize>, // Indices of the correct choices
+    truth: bool,         // Whether the statement is true
+    asked_at: Instant,
+}
+
+pub struct Session {
+    mode: SessionMode,
+    settings: SessionSettings,
+    set_questions: Vec<Question>, // Every question of the set, to draw choices from
+    questions: Vec<Question>,     // Questions in the order they are asked
+    current: usize,               // Index of the question being asked
+    pending: Option<Pending>,
+    results: Vec<QuestionResult>,
+}
+
+impl Session {
+    /// Start a session of `set`'s questions. Only memorize mode asks list
+    /// and ordering questions; the other modes leave them out.
+    pub fn new(set: &AskmeSet, mode: SessionMode, settings: SessionSettings) -> Self {
+        let asked = set
+            .questions
+            .iter()
+            .filter(|question| {
+                mode == SessionMode::Memorize || question.kind == QuestionKind::Single
+            })
+            .cloned()
+            .collect::<Vec<Question>>();
+        let questions = match settings.shuffle {
+            true => shuffle_arr(&asked),
+            false => asked,
+        };
+
+        Session {
+            mode,
+            settings,
+            set_questions: set.all_questions.clone(),
+            questions,
+            current: 0,
+            pending: None,
+            results: Vec::new(),
+        }
+    }
+
+    pub fn mode(&self) -> SessionMode {
+        self.mode
+    }
+
+    /// The question waiting for an answer, or `None` once every question
+    /// has been answered. Asking again gives the same prompt until it is
+    /// answered.
+    pub fn prompt(&mut self) -> Option<Prompt> {
+        if self.pending.is_none() {
+            self.pending = Some(self.ask()?);
+        }
+        self.pending.as_ref().map(|pending| pending.prompt.clone())
+    }
+
+    fn ask(&self) -> Option<Pending> {
+        let question = self.questions.get(self.current)?;
+        let mut pending = Pending {
+            prompt: Prompt {
+                number: self.current + 1,
+                total: self.questions.len(),
+                question: question.title.clone(),
+                kind: PromptKind::Text,
+            },
+            shown: Vec::new(),
+            correct: Vec::new(),
+            truth: false,
+            asked_at: Instant::now(),
+        };
+
+        pending.prompt.kind = match (self.mode, question.kind) {
+            (SessionMode::Memorize, QuestionKind::Single) => PromptKind::Text,
+            (SessionMode::Memorize, QuestionKind::List) => PromptKind::List {
+                items: question.answers.len(),
+            },
+            (SessionMode::Memorize, QuestionKind::Order) => {
+                pending.shown = shuffle_arr(&(0..question.answers.len()).collect::<Vec<usize>>());
+                PromptKind::Order {
+                    items: pending
+                        .shown
+                        .iter()
+                        .map(|&pos| question.answers[pos].clone())
+                        .collect(),
+                }
+            }
+            (SessionMode::Multichoice, _) => {
+                let distractors = self.settings.distractors;
+                match question.select_all.unwrap_or(self.settings.select_all) {
+                    true => {
+                        let (choices, correct) = multiple_select_choices(
+                            question,
+                            &self.set_questions,
+                            self.settings.max_choices,
+                            distractors,
+                        );
+                        pending.correct = correct;
+                        PromptKind::Selection { choices }
+                    }
+                    false => {
+                        // Without enough answers to go around, every question gets them all
+                        let available = choices_available(question, &self.set_questions);
+                        let len = self.settings.max_choices.min(available);
+
+                        let (choices, correct) =
+                            multiple_choices(question, &self.set_questions, len, distractors);
+                        pending.correct = vec![correct];
+                        PromptKind::Choice { choices }
+                    }
+                }
+            }
+            (SessionMode::Correctme, _) => {
+                let (answer, truth) = true_false_statement(question, &self.set_questions);
+                pending.truth = truth;
+                PromptKind::Statement { answer }
+            }
+        };
+
+        Some(pending)
+    }
+
+    /// Grade the answer to the current prompt and move on to the next
+    /// question. Fails if there is no prompt to answer or the answer doesn't
+    /// fit it, in which case the prompt stays open.
+    pub fn answer(&mut self, answer: Answer) -> Result<Feedback, AskmeError> {
+        self.prompt();
+        let pending = match &self.pending {
+            Some(pending) => pending,
+            None => return Err(AskmeError::new("every question was answered", None)),
+        };
+        let question = &self.questions[self.current];
+        let case_sensitive = self.settings.case_sensitive;
+        let mismatch = || {
+            Err(AskmeError::new(
+                format!(
+                    "{:?} doesn't answer a {:?} prompt",
+                    answer, pending.prompt.kind
+                ),
+                None,
+            ))
+        };
+
+        let mut feedback = Feedback {
+            score: 0.0,
+            points: 0.0,
+            correct: question.answers.clone(),
+            missed: Vec::new(),
+            extra: Vec::new(),
+        };
+
+        let given = match (&pending.prompt.kind, &answer) {
+            (PromptKind::Text, Answer::Text(text)) => {
+                let typo_credit = self.settings.scoring.typo_credit;
+                feedback.score = grade_answer(question, text, case_sensitive, typo_credit);
+                text.clone()
+            }
+            (PromptKind::List { .. }, Answer::Items(items)) => {
+                let grade = grade_list(question, items, case_sensitive);
+                feedback.score = grade.score;
+                feedback.missed = grade.missed;
+                feedback.extra = grade.extra;
+                items.join(", ")
+            }
+            (PromptKind::Order { items }, Answer::Order(order)) => {
+                let mut seen = vec![false; items.len()];
+                let is_permutation = order.len() == items.len()
+                    && order
+                        .iter()
+                        .all(|&idx| idx < items.len() && !std::mem::replace(&mut seen[idx], true));
+                if !is_permutation {
+                    return Err(AskmeError::new(
+                        "the order must name every item exactly once",
+                        None,
+                    ));
+                }
+                let positions = order.iter().map(|&i| pending.shown[i]).collect::<Vec<_>>();
+                feedback.score = grade_order(&positions, self.settings.order_scoring);
+                typed_order(order)
+            }
+            (PromptKind::Choice { choices }, Answer::Choice(idx)) => {
+                let choice = match choices.get(*idx) {
+                    Some(choice) => choice,
+                    None => return Err(AskmeError::new("there is no such choice", None)),
+                };
+                if pending.correct == [*idx] {
+                    feedback.score = 1.0;
+                }
+                feedback.correct = picked(choices, &pending.correct);
+                choice.clone()
+            }
+            (PromptKind::Selection { choices }, Answer::Choices(indices)) => {
+                let mut indices = indices.clone();
+                indices.sort();
+                indices.dedup();
+                if indices.iter().any(|&idx| idx >= choices.len()) {
+                    return Err(AskmeError::new("there is no such choice", None));
+                }
+                feedback.score =
+                    grade_selection(&indices, &pending.correct, self.settings.select_scoring);
+                feedback.correct = picked(choices, &pending.correct);
+                picked(choices, &indices).join(", ")
+            }
+            (PromptKind::Statement { .. }, Answer::TrueFalse(yes)) => {
+                if *yes == pending.truth {
+                    feedback.score = 1.0;
+                }
+                match yes {
+                    true => "yes".to_string(),
+                    false => "no".to_string(),
+                }
+            }
+            _ => return mismatch(),
+        };
+
+        // As in the multichoice mode, a selection only counts as wrong if
+        // it earned nothing
+        let wrong_choice = match answer {
+            Answer::Choice(_) => !feedback.is_correct(),
+            Answer::Choices(_) => feedback.score == 0.0,
+            _ => false,
+        };
+        feedback.points = self
+            .settings
+            .scoring
+            .points(question, feedback.score, wrong_choice);
+
+        self.results.push(QuestionResult {
+            title: question.title.clone(),
+            answer: Some(given),
+            score: feedback.score,
+            points: feedback.points,
+            max_points: question.points,
+            response_time: pending.asked_at.elapsed(),
+            timed_out: false,
+        });
+        self.pending = None;
+        self.current += 1;
+
+        Ok(feedback)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.current >= self.questions.len()
+    }
+
+    /// Number of questions answered so far.
+    pub fn answered(&self) -> usize {
+        self.current
+    }
+
+    /// Number of questions in the session.
+    pub fn total(&self) -> usize {
+        self.questions.len()
+    }
+
+    /// Total score so far.
+    pub fn score(&self) -> f64 {
+        self.results.iter().fold(0.0, |total, r| total + r.score)
+    }
+
+    pub fn results(&self) -> &[QuestionResult] {
+        &self.results
+    }
+}
+
+/// The choices at `indices`.
+fn picked(choices: &[String], indices: &[usize]) -> Vec<String> {
+    indices.iter().map(|&idx| choices[idx].clone()).collect()
+}
+
+/// The labels of the items in `order`, as they would be typed.
+fn typed_order(order: &[usize]) -> String {
+    let labels = option_labels(order.len());
+    order
+        .iter()
+        .map(|&idx| labels[idx].as_str())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SET: &str = "
+title: Elements
+subtitle: Symbols
+questions:
+  - title: H
+    answers: [Hydrogen]
+  - title: Noble gases
+    answers: [Helium, Neon, Argon]
+    kind: list
+  - title: By number
+    answers: [Hydrogen, Helium, Lithium]
+    kind: order
+";
+
+    const SINGLE: &str = "
+title: Elements
+subtitle: Symbols
+questions:
+  - title: H
+    answers: [Hydrogen]
+  - title: He
+    answers: [Helium]
+  - title: Li
+    answers: [Lithium]
+";
+
+    fn set(yaml: &str) -> AskmeSet {
+        let mut set: AskmeSet = serde_yaml::from_str(yaml).unwrap();
+        set.all_questions = set.questions.clone();
+        set
+    }
+
+    fn session(yaml: &str, mode: SessionMode, settings: SessionSettings) -> Session {
+        Session::new(&set(yaml), mode, settings)
+    }
+
+    /// Answer the current prompt by typing `input`.
+    fn type_answer(session: &mut Session, input: &str) -> Feedback {
+        let prompt = session.prompt().unwrap();
+        let answer = Answer::parse(&prompt.kind, input).unwrap();
+        session.answer(answer).unwrap()
+    }
+
+    /// The labels of `answers` among `shown`, as they would be typed.
+    fn labels_of(shown: &[String], answers: &[&str]) -> String {
+        let labels = option_labels(shown.len());
+        answers
+            .iter()
+            .map(|answer| {
+                let idx = shown.iter().position(|s| s == answer).unwrap();
+                labels[idx].clone()
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn memorize_grades_each_kind_of_question() {
+        let mut session = session(SET, SessionMode::Memorize, SessionSettings::default());
+
+        let prompt = session.prompt().unwrap();
+        assert_eq!((prompt.number, prompt.total), (1, 3));
+        assert_eq!(prompt.kind, PromptKind::Text);
+        assert!(type_answer(&mut session, " hydrogen ").is_correct());
+
+        assert_eq!(
+            session.prompt().unwrap().kind,
+            PromptKind::List { items: 3 }
+        );
+        let feedback = type_answer(&mut session, "Neon, Helium, Xenon");
+        assert!(!feedback.is_correct());
+        assert_eq!(feedback.missed, vec!["Argon"]);
+        assert_eq!(feedback.extra, vec!["Xenon"]);
+
+        let items = match session.prompt().unwrap().kind {
+            PromptKind::Order { items } => items,
+            kind => panic!("expected an order prompt, got {:?}", kind),
+        };
+        let order = labels_of(&items, &["Hydrogen", "Helium", "Lithium"]);
+        assert!(type_answer(&mut session, &order).is_correct());
+
+        assert!(session.is_finished());
+        assert_eq!(session.prompt(), None);
+        assert_eq!(session.results().len(), 3);
+        assert_eq!(session.results()[0].answer.as_deref(), Some("hydrogen"));
+        assert_eq!(session.results()[2].answer.as_deref(), Some(order.as_str()));
+        assert!(session.score() > 2.0 && session.score() < 3.0);
+    }
+
+    #[test]
+    fn answers_that_dont_fit_leave_the_prompt_open() {
+        let mut session = session(SET, SessionMode::Memorize, SessionSettings::default());
+        let prompt = session.prompt().unwrap();
+
+        assert!(session.answer(Answer::Choice(0)).is_err());
+        assert!(session.answer(Answer::TrueFalse(true)).is_err());
+        assert_eq!(session.prompt(), Some(prompt));
+        assert_eq!(session.answered(), 0);
+
+        session
+            .answer(Answer::Text("Hydrogen".to_string()))
+            .unwrap();
+        session.answer(Answer::Items(Vec::new())).unwrap();
+        assert!(session.answer(Answer::Order(vec![0, 0, 1])).is_err());
+        assert!(session.answer(Answer::Order(vec![2, 1, 0])).is_ok());
+        assert!(session.answer(Answer::Text(String::new())).is_err());
+    }
+
+    #[test]
+    fn multichoice_takes_labels_numbers_and_choices() {
+        let mut session = session(SINGLE, SessionMode::Multichoice, SessionSettings::default());
+
+        for input in ["label", "number", "text"] {
+            let prompt = session.prompt().unwrap();
+            let choices = match &prompt.kind {
+                PromptKind::Choice { choices } => choices.clone(),
+                kind => panic!("expected a choice prompt, got {:?}", kind),
+            };
+            let question = &session.questions[session.current];
+            let correct = choices
+                .iter()
+                .position(|choice| question.answers.contains(choice))
+                .unwrap();
+
+            let input = match input {
+                "label" => labels_of(&choices, &[&choices[correct]]).to_uppercase(),
+                "number" => (correct + 1).to_string(),
+                _ => choices[correct].clone(),
+            };
+            let feedback = type_answer(&mut session, &input);
+            assert!(feedback.is_correct(), "{:?} for {:?}", input, prompt);
+            assert_eq!(feedback.correct, vec![choices[correct].clone()]);
+        }
+    }
+
+    #[test]
+    fn multichoice_labels_choices_past_26_with_numbers() {
+        let mut yaml = "title: Numbers\nsubtitle: s\nquestions:\n".to_string();
+        for n in 0..30 {
+            yaml.push_str(&format!("  - title: Q{}\n    answers: [A{}]\n", n, n));
+        }
+        let settings = SessionSettings {
+            max_choices: 30,
+            ..SessionSettings::default()
+        };
+        let mut session = session(&yaml, SessionMode::Multichoice, settings);
+
+        let choices = match session.prompt().unwrap().kind {
+            PromptKind::Choice { choices } => choices,
+            kind => panic!("expected a choice prompt, got {:?}", kind),
+        };
+        assert_eq!(choices.len(), 30);
+        let correct = choices.iter().position(|c| c == "A0").unwrap();
+
+        // "a" is no label when there are numbers
+        let kind = PromptKind::Choice {
+            choices: choices.clone(),
+        };
+        assert_eq!(Answer::parse(&kind, "a"), None);
+        assert!(type_answer(&mut session, &(correct + 1).to_string()).is_correct());
+    }
+
+    #[test]
+    fn select_all_grades_every_pick() {
+        let yaml = "
+title: Elements
+subtitle: Symbols
+questions:
+  - title: Noble gases
+    answers: [Helium, Neon]
+    select_all: true
+  - title: Metals
+    answers: [Lithium, Sodium]
+  - title: Halogens
+    answers: [Fluorine]
+";
+        let settings = SessionSettings {
+            max_choices: 5,
+            ..SessionSettings::default()
+        };
+        let mut session = session(yaml, SessionMode::Multichoice, settings);
+
+        let choices = match session.prompt().unwrap().kind {
+            PromptKind::Selection { choices } => choices,
+            kind => panic!("expected a selection prompt, got {:?}", kind),
+        };
+        let right = choices
+            .iter()
+            .filter(|c| ["Helium", "Neon"].contains(&c.as_str()))
+            .map(String::as_str)
+            .collect::<Vec<&str>>();
+        let wrong = choices
+            .iter()
+            .find(|c| !["Helium", "Neon"].contains(&c.as_str()))
+            .unwrap();
+
+        // Every right pick earns a share, and the wrong one takes one away
+        let mut picks = right.clone();
+        picks.push(wrong);
+        let feedback = type_answer(&mut session, &labels_of(&choices, &picks));
+        let expected = (right.len() as f64 - 1.0) / right.len() as f64;
+        assert_eq!(feedback.score, expected);
+        assert_eq!(feedback.correct.len(), right.len());
+
+        // The other questions ask for one choice
+        assert!(matches!(
+            session.prompt().unwrap().kind,
+            PromptKind::Choice { .. }
+        ));
+    }
+
+    #[test]
+    fn select_all_can_be_set_for_every_question() {
+        let settings = SessionSettings {
+            select_all: true,
+            select_scoring: SelectScoring::Exact,
+            ..SessionSettings::default()
+        };
+        let mut session = session(SINGLE, SessionMode::Multichoice, settings);
+
+        let choices = match session.prompt().unwrap().kind {
+            PromptKind::Selection { choices } => choices,
+            kind => panic!("expected a selection prompt, got {:?}", kind),
+        };
+        let picks = labels_of(&choices, &["Hydrogen"]);
+        assert!(type_answer(&mut session, &picks).is_correct());
+
+        assert!(session.answer(Answer::Choices(vec![99])).is_err());
+        assert!(session.answer(Answer::Choice(0)).is_err());
+        assert_eq!(session.answered(), 1);
+    }
+
+    #[test]
+    fn only_memorize_asks_list_and_order_questions() {
+        for mode in [SessionMode::Multichoice, SessionMode::Correctme] {
+            let mut session = session(SET, mode, SessionSettings::default());
+            assert_eq!(session.prompt().unwrap().total, 1);
+            assert_eq!(session.questions[0].title, "H");
+        }
+    }
+
+    #[test]
+    fn correctme_grades_the_statement() {
+        let mut session = session(SINGLE, SessionMode::Correctme, SessionSettings::default());
+
+        while let Some(prompt) = session.prompt() {
+            let answer = match prompt.kind {
+                PromptKind::Statement { answer } => answer,
+                kind => panic!("expected a statement prompt, got {:?}", kind),
+            };
+            let question = &session.questions[session.current];
+            let truth = question.answers.contains(&answer);
+
+            let input = if truth { "yes" } else { "no" };
+            assert!(type_answer(&mut session, input).is_correct());
+        }
+        assert_eq!(session.score(), 3.0);
+    }
+}
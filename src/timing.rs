@@ -0,0 +1,63 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Time limits.
+use std::time::{Duration, Instant};
+
+/// Keeps track of the per-question and whole-session time limits.
+pub struct Clock {
+    question_limit: Option<Duration>,
+    session_limit: Option<Duration>,
+    started: Instant,
+}
+
+impl Clock {
+    /// Create a clock from limits in seconds, which must be valid settings
+    /// (see `Options::validate`). The session time starts counting down when
+    /// the clock is created or `start`ed.
+    pub fn new(question_limit: Option<f64>, session_limit: Option<f64>) -> Self {
+        Clock {
+            question_limit: question_limit.map(Duration::from_secs_f64),
+            session_limit: session_limit.map(Duration::from_secs_f64),
+            started: Instant::now(),
+        }
+    }
+
+    pub fn start(&mut self) {
+        self.started = Instant::now();
+    }
+
+    pub fn is_limited(&self) -> bool {
+        self.question_limit.is_some() || self.session_limit.is_some()
+    }
+
+    /// Whether the session time limit has run out.
+    pub fn is_over(&self) -> bool {
+        match self.session_limit {
+            Some(limit) => self.started.elapsed() >= limit,
+            None => false,
+        }
+    }
+
+    /// The deadline for answering a question asked now, if there is any.
+    pub fn question_deadline(&self) -> Option<Instant> {
+        let question_deadline = self.question_limit.map(|limit| Instant::now() + limit);
+        let session_deadline = self.session_limit.map(|limit| self.started + limit);
+
+        match (question_deadline, session_deadline) {
+            (Some(q), Some(s)) => Some(q.min(s)),
+            (q, s) => q.or(s),
+        }
+    }
+}
@@ -17,11 +17,15 @@ use crate::prelude::*;
 pub trait AskmeMode<S, R> {
     fn get_title(&self) -> String;
     fn get_subtitle(&self) -> String;
+    fn get_asked_count(&self) -> usize;
+    fn get_set(&self) -> &AskmeSet;
+    fn get_results(&self) -> &[QuestionResult];
     fn new(set: AskmeSet, settings: S) -> Self;
     fn run_set(&mut self);
-    fn run(&mut self) -> Result<R, &str>;
+    fn run(&mut self) -> Result<R, AskmeError>;
 }
 
 pub trait FromSetFile {
     fn from_file(file_name: &str) -> Result<AskmeSet, AskmeError>;
+    fn from_files(file_names: &[String]) -> Result<AskmeSet, AskmeError>;
 }
@@ -0,0 +1,635 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Full-screen terminal frontend.
+use std::io::{self, Stdout};
+use std::time::{Duration, Instant};
+
+use crossterm::{
+    cursor::Show,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span as TextSpan, Spans},
+    widgets::{Block, Borders, Gauge, List, ListItem, ListState, Paragraph, Wrap},
+    Frame, Terminal,
+};
+
+use crate::commands;
+use crate::format_score;
+use crate::prelude::*;
+use crate::ui::{labelled, LineUi, Span, Tone, Ui};
+
+type Backend = CrosstermBackend<Stdout>;
+
+const RECENT_RESULTS: usize = 50;
+
+/// What the answer area is waiting for.
+enum Input<'a> {
+    Idle,
+    Text {
+        message: &'a str,
+        text: &'a str,
+        remaining: Option<Duration>,
+    },
+    Select {
+        message: &'a str,
+        options: &'a [String],
+        selected: usize,
+        checked: Option<&'a [bool]>, // Options ticked so far, when several can be picked
+    },
+    Confirm {
+        message: &'a str,
+    },
+    AnyKey {
+        message: &'a str,
+    },
+}
+
+/// A header with the set title, progress and score, the current question
+/// with its feedback, an answer area and a sidebar of recent results.
+pub struct TuiUi {
+    terminal: Terminal<Backend>,
+    title: String,
+    subtitle: String,
+    log: Vec<Vec<Span>>,              // Every line printed, in order
+    question_start: usize,            // First line of the current question
+    summary_start: usize,             // First line printed after the last prompt
+    progress: Option<(usize, usize)>, // Questions done and total in this pass
+    results: Vec<QuestionResult>,     // Outcome of every question so far
+}
+
+fn restore_terminal() {
+    let _ = terminal::disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, Show);
+}
+
+fn style(tone: Tone) -> Style {
+    let style = Style::default();
+
+    match tone {
+        Tone::Plain => style,
+        Tone::Bold => style.add_modifier(Modifier::BOLD),
+        Tone::Emphasis => style.add_modifier(Modifier::ITALIC | Modifier::BOLD),
+        Tone::Title => style.fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        Tone::Subtitle => style.fg(Color::Blue),
+        Tone::Highlight => style.fg(Color::LightMagenta),
+        Tone::Correct => style.fg(Color::Green),
+        Tone::Partial => style.fg(Color::Yellow),
+        Tone::Incorrect => style.fg(Color::Red),
+    }
+}
+
+fn to_spans(line: &[Span]) -> Spans<'static> {
+    Spans(
+        line.iter()
+            .map(|span| TextSpan::styled(span.text.clone(), style(span.tone)))
+            .collect(),
+    )
+}
+
+fn result_tone(score: f64) -> Tone {
+    match score {
+        s if s >= 1.0 => Tone::Correct,
+        s if s > 0.0 => Tone::Partial,
+        _ => Tone::Incorrect,
+    }
+}
+
+/// Index of the option labelled with the letter `c`, if any.
+fn letter_index(labels: &[String], c: char) -> Option<usize> {
+    match c.is_ascii_alphabetic() {
+        true => labels.iter().position(|l| *l == c.to_string()),
+        false => None,
+    }
+}
+
+/// Number of rows `lines` take up once wrapped to `width` columns.
+fn wrapped_height(lines: &[Vec<Span>], width: u16) -> usize {
+    let width = usize::from(width.max(1));
+
+    lines
+        .iter()
+        .map(|line| {
+            let len = line.iter().map(|s| s.text.chars().count()).sum::<usize>();
+            len.max(1).div_ceil(width)
+        })
+        .sum()
+}
+
+impl TuiUi {
+    pub fn new() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen)?;
+
+        // Leave the terminal usable if anything panics
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore_terminal();
+            hook(info);
+        }));
+
+        Ok(TuiUi {
+            terminal: Terminal::new(CrosstermBackend::new(io::stdout()))?,
+            title: String::new(),
+            subtitle: String::new(),
+            log: Vec::new(),
+            question_start: 0,
+            summary_start: 0,
+            progress: None,
+            results: Vec::new(),
+        })
+    }
+
+    fn draw(&mut self, input: &Input) {
+        let TuiUi {
+            terminal,
+            title,
+            subtitle,
+            log,
+            question_start,
+            progress,
+            results,
+            ..
+        } = self;
+
+        let drawn = terminal.draw(|frame| {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(3), Constraint::Min(0)])
+                .split(frame.size());
+            draw_header(frame, rows[0], title, subtitle, *progress, results);
+
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
+                .split(rows[1]);
+            draw_results(frame, columns[1], results);
+
+            let answer_height = match input {
+                Input::Select { options, .. } => (options.len() as u16 + 2).min(12),
+                _ => 3,
+            };
+            let main = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(answer_height)])
+                .split(columns[0]);
+            draw_question(frame, main[0], &log[*question_start..]);
+            draw_input(frame, main[1], input);
+        });
+
+        if drawn.is_err() {
+            restore_terminal();
+            panic!("Failed to draw the terminal UI!");
+        }
+    }
+
+    /// Wait for a key press, redrawing at least every `timeout`.
+    fn next_key(&mut self, timeout: Duration) -> Option<KeyEvent> {
+        let key = match event::poll(timeout) {
+            Ok(true) => match event::read() {
+                Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => Some(key),
+                Ok(_) => None,
+                Err(_) => panic!("Failed to read from stdin!"),
+            },
+            Ok(false) => None,
+            Err(_) => panic!("Failed to read from stdin!"),
+        };
+
+        // Ctrl+C ends the session like `:quit`, so the summary is still shown
+        if let Some(KeyEvent {
+            code: KeyCode::Char('c'),
+            modifiers,
+            ..
+        }) = key
+        {
+            if modifiers.contains(KeyModifiers::CONTROL) {
+                commands::quit();
+            }
+        }
+
+        key
+    }
+
+    /// Mark the end of a prompt: whatever is printed after it is the summary.
+    fn prompted(&mut self) {
+        self.summary_start = self.log.len();
+    }
+}
+
+fn draw_header(
+    frame: &mut Frame<Backend>,
+    area: Rect,
+    title: &str,
+    subtitle: &str,
+    progress: Option<(usize, usize)>,
+    results: &[QuestionResult],
+) {
+    let block = Block::default().borders(Borders::ALL).title(Spans(vec![
+        TextSpan::styled(format!(" {} ", title), style(Tone::Title)),
+        TextSpan::styled(format!("— {} ", subtitle), style(Tone::Subtitle)),
+    ]));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(24)])
+        .split(inner);
+
+    let (done, total) = progress.unwrap_or((0, 0));
+    let ratio = match total {
+        0 => 0.0,
+        total => (done as f64 / total as f64).min(1.0),
+    };
+    let gauge = Gauge::default()
+        .gauge_style(style(Tone::Highlight))
+        .ratio(ratio)
+        .label(format!("{}/{}", done, total));
+    frame.render_widget(gauge, columns[0]);
+
+    let score = results.iter().fold(0.0, |total, r| total + r.score);
+    let score = Paragraph::new(TextSpan::styled(
+        format!(" Score: {}/{}", format_score(score), results.len()),
+        style(Tone::Highlight),
+    ));
+    frame.render_widget(score, columns[1]);
+}
+
+fn draw_results(frame: &mut Frame<Backend>, area: Rect, results: &[QuestionResult]) {
+    let items = results
+        .iter()
+        .rev()
+        .take(RECENT_RESULTS)
+        .map(|result| {
+            let mark = match result_tone(result.score) {
+                Tone::Correct => "✔",
+                Tone::Partial => "➖",
+                _ => "✘",
+            };
+            ListItem::new(Spans(vec![
+                TextSpan::styled(format!("{} ", mark), style(result_tone(result.score))),
+                TextSpan::raw(format!(
+                    "{} ({:.1}s)",
+                    result.title,
+                    result.response_time.as_secs_f64()
+                )),
+            ]))
+        })
+        .collect::<Vec<ListItem>>();
+
+    let list = List::new(items).block(Block::default().borders(Borders::ALL).title(" Recent "));
+    frame.render_widget(list, area);
+}
+
+fn draw_question(frame: &mut Frame<Backend>, area: Rect, lines: &[Vec<Span>]) {
+    let block = Block::default().borders(Borders::ALL).title(" Question ");
+    let inner = block.inner(area);
+
+    // Keep the latest lines in view
+    let height = wrapped_height(lines, inner.width);
+    let scroll = height.saturating_sub(usize::from(inner.height)) as u16;
+
+    let text = lines
+        .iter()
+        .map(|line| to_spans(line))
+        .collect::<Vec<Spans>>();
+    let paragraph = Paragraph::new(text)
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .scroll((scroll, 0));
+    frame.render_widget(paragraph, area);
+}
+
+fn draw_input(frame: &mut Frame<Backend>, area: Rect, input: &Input) {
+    let block = Block::default().borders(Borders::ALL);
+
+    match input {
+        Input::Idle => frame.render_widget(block, area),
+        Input::Text {
+            message,
+            text,
+            remaining,
+        } => {
+            let mut title = vec![TextSpan::raw(format!(" {} ", message))];
+            if let Some(remaining) = remaining {
+                let secs = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+                let tone = match secs {
+                    0..=5 => Tone::Incorrect,
+                    _ => Tone::Partial,
+                };
+                title.push(TextSpan::styled(format!("[{}s] ", secs), style(tone)));
+            }
+
+            let block = block.title(Spans(title));
+            let inner = block.inner(area);
+            frame.render_widget(Paragraph::new(format!("> {}", text)).block(block), area);
+
+            let cursor = inner.x + 2 + text.chars().count() as u16;
+            frame.set_cursor(cursor.min(inner.right().saturating_sub(1)), inner.y);
+        }
+        Input::Select {
+            message,
+            options,
+            selected,
+            checked,
+        } => {
+            let items = options
+                .iter()
+                .enumerate()
+                .map(|(idx, option)| match checked {
+                    Some(checked) if checked[idx] => ListItem::new(format!("[x] {}", option)),
+                    Some(_) => ListItem::new(format!("[ ] {}", option)),
+                    None => ListItem::new(option.as_str()),
+                })
+                .collect::<Vec<ListItem>>();
+            let list = List::new(items)
+                .block(block.title(format!(" {} ", message)))
+                .highlight_style(style(Tone::Highlight).add_modifier(Modifier::BOLD))
+                .highlight_symbol("> ");
+
+            let mut state = ListState::default();
+            state.select(Some(*selected));
+            frame.render_stateful_widget(list, area, &mut state);
+        }
+        Input::Confirm { message } => {
+            let paragraph = Paragraph::new(format!("{} (y/n)", message)).block(block);
+            frame.render_widget(paragraph, area);
+        }
+        Input::AnyKey { message } => {
+            let paragraph = Paragraph::new(TextSpan::styled(*message, style(Tone::Bold)));
+            frame.render_widget(paragraph.block(block), area);
+        }
+    }
+}
+
+impl Ui for TuiUi {
+    fn show_title(&mut self, title: &str, subtitle: &str) {
+        self.title = title.to_string();
+        self.subtitle = subtitle.to_string();
+        self.draw(&Input::Idle);
+    }
+
+    fn show_question(&mut self, question: &str) {
+        self.question_start = self.log.len();
+        self.log.push(vec![Span::new(Tone::Bold, question)]);
+        self.draw(&Input::Idle);
+    }
+
+    fn print(&mut self, spans: &[Span]) {
+        self.log.push(spans.to_vec());
+        self.draw(&Input::Idle);
+    }
+
+    fn read_line(&mut self, message: &str, deadline: Option<Instant>) -> Option<String> {
+        let mut text = String::new();
+
+        let answer = loop {
+            let remaining = deadline.map(|d| d.saturating_duration_since(Instant::now()));
+            if remaining == Some(Duration::ZERO) {
+                break None;
+            }
+
+            self.draw(&Input::Text {
+                message,
+                text: &text,
+                remaining,
+            });
+
+            // Wake up regularly to redraw the countdown
+            let timeout = remaining.unwrap_or(Duration::MAX);
+            let key = match self.next_key(timeout.min(Duration::from_millis(200))) {
+                Some(key) => key,
+                None => continue,
+            };
+
+            match key.code {
+                _ if commands::has_quit() => break None,
+                KeyCode::Enter => break Some(text),
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                KeyCode::Char(c) => text.push(c),
+                _ => (),
+            }
+        };
+
+        if answer.is_none() && !commands::has_quit() {
+            self.log
+                .push(vec![Span::new(Tone::Incorrect, "⏰ Time's up!")]);
+        }
+        self.prompted();
+        self.draw(&Input::Idle);
+        answer
+    }
+
+    fn select(&mut self, message: &str, options: &[String]) -> usize {
+        let mut selected = 0;
+
+        let choice = loop {
+            self.draw(&Input::Select {
+                message,
+                options,
+                selected,
+                checked: None,
+            });
+
+            let key = match self.next_key(Duration::from_millis(200)) {
+                Some(key) => key,
+                None => continue,
+            };
+
+            match key.code {
+                _ if commands::has_quit() => break selected,
+                KeyCode::Up | KeyCode::Char('k') => selected = selected.saturating_sub(1),
+                KeyCode::Down | KeyCode::Char('j') => {
+                    selected = (selected + 1).min(options.len().saturating_sub(1))
+                }
+                KeyCode::Home => selected = 0,
+                KeyCode::End => selected = options.len().saturating_sub(1),
+                KeyCode::Enter => break selected,
+                _ => (),
+            }
+        };
+
+        self.prompted();
+        self.draw(&Input::Idle);
+        choice
+    }
+
+    fn select_labelled(&mut self, message: &str, options: &[String], labels: &[String]) -> usize {
+        let mut selected = 0;
+        let shown = labelled(options, labels);
+
+        let choice = loop {
+            self.draw(&Input::Select {
+                message,
+                options: &shown,
+                selected,
+                checked: None,
+            });
+
+            let key = match self.next_key(Duration::from_millis(200)) {
+                Some(key) => key,
+                None => continue,
+            };
+
+            // Typing the letter of an option picks it right away
+            let letter = match key.code {
+                KeyCode::Char(c) => letter_index(labels, c),
+                _ => None,
+            };
+            match (key.code, letter) {
+                _ if commands::has_quit() => break selected,
+                (_, Some(idx)) => break idx,
+                (KeyCode::Up, _) => selected = selected.saturating_sub(1),
+                (KeyCode::Down, _) => {
+                    selected = (selected + 1).min(options.len().saturating_sub(1))
+                }
+                (KeyCode::Home, _) => selected = 0,
+                (KeyCode::End, _) => selected = options.len().saturating_sub(1),
+                (KeyCode::Enter, _) => break selected,
+                _ => (),
+            }
+        };
+
+        self.prompted();
+        self.draw(&Input::Idle);
+        choice
+    }
+
+    fn multi_select(&mut self, message: &str, options: &[String], labels: &[String]) -> Vec<usize> {
+        let mut selected = 0;
+        let mut checked = vec![false; options.len()];
+        let message = format!("{} (space or letter to pick, enter to submit)", message);
+        let shown = labelled(options, labels);
+
+        loop {
+            self.draw(&Input::Select {
+                message: &message,
+                options: &shown,
+                selected,
+                checked: Some(&checked),
+            });
+
+            let key = match self.next_key(Duration::from_millis(200)) {
+                Some(key) => key,
+                None => continue,
+            };
+
+            let letter = match key.code {
+                KeyCode::Char(c) => letter_index(labels, c),
+                _ => None,
+            };
+            match (key.code, letter) {
+                _ if commands::has_quit() => break,
+                (_, Some(idx)) => {
+                    selected = idx;
+                    checked[selected] = !checked[selected];
+                }
+                (KeyCode::Up, _) => selected = selected.saturating_sub(1),
+                (KeyCode::Down, _) => {
+                    selected = (selected + 1).min(options.len().saturating_sub(1))
+                }
+                (KeyCode::Home, _) => selected = 0,
+                (KeyCode::End, _) => selected = options.len().saturating_sub(1),
+                (KeyCode::Char(' '), _) if !checked.is_empty() => {
+                    checked[selected] = !checked[selected]
+                }
+                (KeyCode::Enter, _) => break,
+                _ => (),
+            }
+        }
+
+        self.prompted();
+        self.draw(&Input::Idle);
+        (0..options.len()).filter(|&idx| checked[idx]).collect()
+    }
+
+    fn confirm(&mut self, message: &str) -> bool {
+        let answer = loop {
+            self.draw(&Input::Confirm { message });
+
+            let key = match self.next_key(Duration::from_millis(200)) {
+                Some(key) => key,
+                None => continue,
+            };
+
+            match key.code {
+                _ if commands::has_quit() => break false,
+                KeyCode::Char('y') | KeyCode::Char('Y') => break true,
+                KeyCode::Char('n') | KeyCode::Char('N') => break false,
+                _ => (),
+            }
+        };
+
+        self.prompted();
+        self.draw(&Input::Idle);
+        answer
+    }
+
+    fn pause(&mut self, secs: f64) {
+        self.draw(&Input::Idle);
+        std::thread::sleep(Duration::from_secs_f64(secs));
+        self.prompted();
+    }
+
+    fn show_progress(&mut self, done: usize, total: usize) {
+        self.progress = Some((done, total));
+        self.draw(&Input::Idle);
+    }
+
+    fn show_result(&mut self, result: &QuestionResult) {
+        self.results.push(result.clone());
+        if let Some((done, total)) = self.progress {
+            self.progress = Some((done + 1, total));
+        }
+        self.draw(&Input::Idle);
+    }
+
+    fn undo_results(&mut self, count: usize) {
+        self.results
+            .truncate(self.results.len().saturating_sub(count));
+        if let Some((done, total)) = self.progress {
+            self.progress = Some((done.saturating_sub(count), total));
+        }
+        self.draw(&Input::Idle);
+    }
+
+    /// Wait for a key press, then leave the full-screen UI and print what
+    /// was shown after the last prompt (reviews and summaries).
+    fn finish(&mut self) {
+        // Show the whole summary, not just the last question
+        self.question_start = self.summary_start;
+        let message = "Press any key to exit";
+        loop {
+            self.draw(&Input::AnyKey { message });
+            if self.next_key(Duration::from_millis(200)).is_some() {
+                break;
+            }
+        }
+
+        restore_terminal();
+
+        let mut line_ui = LineUi;
+        for line in &self.log[self.summary_start..] {
+            line_ui.print(line);
+        }
+    }
+}
@@ -12,16 +12,50 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::fs;
+use std::time::Duration;
 
+use crate::config::Options;
+use crate::history::canonical_set_file;
 use crate::prelude::AskmeError;
 use crate::prelude::FromSetFile;
 
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum QuestionKind {
+    #[default]
+    Single, // Any one of the answers is correct
+    List,  // All of the answers must be given
+    Order, // The answers must be put in order
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+fn default_points() -> f64 {
+    1.0
+}
+
 #[derive(Deserialize, Clone)]
 pub struct Question {
     pub title: String,        // Question title
     pub answers: Vec<String>, // List of answers
+    #[serde(default)]
+    pub kind: QuestionKind, // Question kind
+    #[serde(default)]
+    pub ordered: bool, // Whether list items must be given in order (list questions only)
+    #[serde(default)]
+    pub tags: Vec<String>, // Tags used to group questions in statistics
+    #[serde(default = "default_weight", alias = "difficulty")]
+    pub weight: f64, // How often the question comes up in adaptive mode, relative to others
+    #[serde(default = "default_points")]
+    pub points: f64, // Points a fully correct answer is worth
+    #[serde(default)]
+    pub select_all: Option<bool>, // Whether multiple choice asks for every correct option (overrides the settings)
+    #[serde(skip)]
+    pub source: String, // Path of the set file the question was loaded from
 }
 
 #[derive(Deserialize)]
@@ -29,6 +63,51 @@ pub struct AskmeSet {
     pub title: String,            // Question title
     pub subtitle: String,         // Question subtitle
     pub questions: Vec<Question>, // List of questions
+    #[serde(default)]
+    pub question_time: Option<f64>, // Default time limit per question (seconds)
+    #[serde(default)]
+    pub time_limit: Option<f64>, // Default time limit for the whole session (seconds)
+    #[serde(default)]
+    pub pass_mark: Option<f64>, // Default exam pass mark (percentage)
+    #[serde(default)]
+    pub settings: Options, // Settings that apply whenever this set is run
+    #[serde(skip)]
+    pub all_questions: Vec<Question>, // Every question of the set, including ones left out of this session
+}
+
+impl AskmeSet {
+    /// The settings this set asks for, including its time limits and pass mark.
+    pub fn options(&self) -> Options {
+        let limits = Options {
+            question_time: self.question_time,
+            time_limit: self.time_limit,
+            pass_mark: self.pass_mark,
+            ..Options::default()
+        };
+
+        limits.merge(&self.settings)
+    }
+
+    /// Leave out the list and ordering questions, for modes that only ask for
+    /// one answer. Returns how many were left out.
+    pub fn keep_single_questions(&mut self) -> usize {
+        let total = self.questions.len();
+        self.questions
+            .retain(|question| question.kind == QuestionKind::Single);
+        total - self.questions.len()
+    }
+}
+
+/// Outcome of asking a single question.
+#[derive(Clone)]
+pub struct QuestionResult {
+    pub title: String,           // Question title
+    pub answer: Option<String>,  // The user's answer, if one was given
+    pub score: f64,              // Score earned, from 0.0 to 1.0
+    pub points: f64,             // Points earned (negative for a penalized wrong answer)
+    pub max_points: f64,         // Points the question is worth
+    pub response_time: Duration, // Time taken to answer
+    pub timed_out: bool,         // Whether the time ran out before answering
 }
 
 impl FromSetFile for AskmeSet {
@@ -43,7 +122,7 @@ impl FromSetFile for AskmeSet {
             }
         };
 
-        let content: AskmeSet = match serde_yaml::from_str(&yaml_file) {
+        let mut content: AskmeSet = match serde_yaml::from_str(&yaml_file) {
             Ok(file_struct) => file_struct,
             Err(e) => {
                 return Err(AskmeError::new(
@@ -53,6 +132,59 @@ impl FromSetFile for AskmeSet {
             }
         };
 
+        if let Some(question) = content.questions.iter().find(|qn| qn.answers.is_empty()) {
+            return Err(AskmeError::new(
+                format!(
+                    "Question '{}' in '{}' has no answers",
+                    question.title, file_name
+                ),
+                None,
+            ));
+        }
+
+        let source = canonical_set_file(file_name);
+        for question in &mut content.questions {
+            question.source = source.clone();
+        }
+        content.all_questions = content.questions.clone();
+
         Ok(content)
     }
+
+    fn from_files(file_names: &[String]) -> Result<AskmeSet, AskmeError> {
+        let mut sets = file_names
+            .iter()
+            .map(|file_name| AskmeSet::from_file(file_name))
+            .collect::<Result<Vec<AskmeSet>, AskmeError>>()?;
+
+        if sets.len() == 1 {
+            return Ok(sets.remove(0));
+        }
+
+        // The first set's settings apply to the merged set
+        let mut merged = match sets.first() {
+            Some(first) => AskmeSet {
+                title: sets
+                    .iter()
+                    .map(|set| set.title.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(" + "),
+                subtitle: first.subtitle.clone(),
+                questions: Vec::new(),
+                question_time: first.question_time,
+                time_limit: first.time_limit,
+                pass_mark: first.pass_mark,
+                settings: first.settings.clone(),
+                all_questions: Vec::new(),
+            },
+            None => return Err(AskmeError::new("No set files given", None)),
+        };
+
+        for set in sets {
+            merged.questions.extend(set.questions);
+            merged.all_questions.extend(set.all_questions);
+        }
+
+        Ok(merged)
+    }
 }
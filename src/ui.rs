@@ -0,0 +1,724 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Frontends the modes print to and read from.
+///
+/// The modes never talk to the terminal themselves: everything goes through
+/// the functions of this module, which hand it to the frontend picked with
+/// `set_ui` (the line-based one by default).
+use std::cell::{Cell, RefCell};
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+use colored::{ColoredString, Colorize};
+use crossterm::{
+    cursor::MoveToColumn,
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    queue,
+    style::Print,
+    terminal::{self, Clear, ClearType},
+};
+use figlet_rs::FIGfont;
+use inquire::{Confirm, InquireError, MultiSelect, Select, Text};
+use serde::Deserialize;
+
+use crate::commands;
+use crate::format_score;
+use crate::grading::{option_labels, parse_option, parse_options};
+use crate::machine::MachineUi;
+use crate::prelude::*;
+use crate::scoring::Summary;
+use crate::tui::TuiUi;
+
+/// How a piece of text is shown.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Tone {
+    Plain,
+    Bold,
+    Emphasis,  // Quoted statements and revealed answers
+    Title,     // Set title
+    Subtitle,  // Set subtitle
+    Highlight, // Scores and progress
+    Correct,
+    Partial,
+    Incorrect,
+}
+
+/// A piece of text shown in one tone.
+#[derive(Clone, Debug)]
+pub struct Span {
+    pub tone: Tone,
+    pub text: String,
+}
+
+impl Span {
+    pub fn new<S: Into<String>>(tone: Tone, text: S) -> Self {
+        Span {
+            tone,
+            text: text.into(),
+        }
+    }
+
+    pub fn plain<S: Into<String>>(text: S) -> Self {
+        Span::new(Tone::Plain, text)
+    }
+}
+
+/// Which frontend to use.
+#[derive(Clone, Copy, Default, PartialEq, Debug, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UiKind {
+    #[default]
+    Line, // Line-by-line prompts
+    Tui, // Full-screen terminal UI
+    #[value(skip)]
+    #[serde(skip)]
+    Machine, // JSON lines for other programs (--machine)
+}
+
+/// Kinds of messages about the program itself rather than the questions.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Notice {
+    Info,
+    Warning,
+    Error,
+}
+
+/// A frontend: shows what the modes print and reads the user's answers.
+///
+/// When the user quits at a prompt (e.g. with Ctrl+C), the frontend calls
+/// `commands::quit` and returns right away; what it returns is then ignored,
+/// and the session ends as with `:quit`.
+pub trait Ui {
+    fn show_title(&mut self, title: &str, subtitle: &str);
+
+    /// Show the question about to be asked.
+    fn show_question(&mut self, question: &str);
+
+    /// Show a line of text.
+    fn print(&mut self, spans: &[Span]);
+
+    /// Read a line of text, giving up at `deadline` if there is one. Returns
+    /// `None` if the time ran out.
+    fn read_line(&mut self, message: &str, deadline: Option<Instant>) -> Option<String>;
+
+    /// Let the user pick one of `options`, returning its index.
+    fn select(&mut self, message: &str, options: &[String]) -> usize;
+
+    /// Let the user pick one of `options`, shown with `labels` (see
+    /// `option_labels`), returning its index. The label can be typed to pick
+    /// the option.
+    fn select_labelled(&mut self, message: &str, options: &[String], labels: &[String]) -> usize {
+        self.select(message, &labelled(options, labels))
+    }
+
+    /// Let the user pick any number of `options`, shown with `labels`,
+    /// returning their indices in order.
+    fn multi_select(&mut self, message: &str, options: &[String], labels: &[String]) -> Vec<usize>;
+
+    /// Whether `select` and the like can be used. Otherwise, the options have
+    /// to be printed and picked by typing.
+    fn has_menus(&self) -> bool {
+        true
+    }
+
+    /// Ask the user a yes/no question.
+    fn confirm(&mut self, message: &str) -> bool;
+
+    fn pause(&mut self, secs: f64) {
+        std::thread::sleep(Duration::from_secs_f64(secs));
+    }
+
+    /// Show how far along the current pass is: `done` out of `total`
+    /// questions.
+    fn show_progress(&mut self, _done: usize, _total: usize) {}
+
+    /// Show the outcome of a question once it has been graded.
+    fn show_result(&mut self, _result: &QuestionResult) {}
+
+    /// Forget the last `count` results shown, after `:undo`.
+    fn undo_results(&mut self, _count: usize) {}
+
+    /// Show the totals of the session and the grade they earn.
+    fn show_score(&mut self, summary: &Summary, grade: &str) {
+        self.print(&[Span::new(
+            Tone::Highlight,
+            format!(
+                " Correct answers: {}/{}",
+                format_score(summary.correct),
+                summary.asked
+            ),
+        )]);
+        self.print(&[
+            Span::new(
+                Tone::Highlight,
+                format!(
+                    " Points: {}/{} ({:.1}%)",
+                    format_score(summary.points),
+                    format_score(summary.max_points),
+                    summary.percentage()
+                ),
+            ),
+            Span::plain(" grade "),
+            Span::new(Tone::Bold, grade),
+        ]);
+    }
+
+    fn notify(&mut self, notice: Notice, message: &str) {
+        let label = match notice {
+            Notice::Info => Span::plain("[i] info: "),
+            Notice::Warning => Span::new(Tone::Bold, "[!] warning: "),
+            Notice::Error => Span::new(Tone::Incorrect, "[!!] error: "),
+        };
+        let message = match notice {
+            Notice::Info => Span::new(Tone::Bold, message),
+            Notice::Warning => Span::new(Tone::Partial, message),
+            Notice::Error => Span::plain(message),
+        };
+        self.print(&[label, message]);
+    }
+
+    /// Called once the session is over, before the final score is printed.
+    fn finish(&mut self) {}
+}
+
+/// The line-based frontend: figlet title, `inquire` prompts and colored text.
+pub struct LineUi;
+
+fn colorize(span: &Span) -> ColoredString {
+    let text = span.text.as_str();
+
+    match span.tone {
+        Tone::Plain => text.normal(),
+        Tone::Bold => text.bold(),
+        Tone::Emphasis => text.italic().bold(),
+        Tone::Title => text.cyan(),
+        Tone::Subtitle => text.blue(),
+        Tone::Highlight => text.bright_purple(),
+        Tone::Correct => text.green(),
+        Tone::Partial => text.yellow(),
+        Tone::Incorrect => text.red(),
+    }
+}
+
+/// End the session if the user pressed Ctrl+C at an `inquire` prompt.
+fn quit_on_interrupt(err: InquireError) {
+    match err {
+        InquireError::OperationInterrupted => commands::quit(),
+        _ => panic!("Failed to read from stdin!"),
+    }
+}
+
+/// `options` with their `labels` in front, as in "a) Helium".
+pub fn labelled(options: &[String], labels: &[String]) -> Vec<String> {
+    labels
+        .iter()
+        .zip(options)
+        .map(|(label, option)| format!("{}) {}", label, option))
+        .collect()
+}
+
+/// Filter for a menu of labelled options: typing a label leaves only its
+/// option, anything else searches the options. A number more digits could
+/// still make another label of, like the "1" of "12", also keeps the options
+/// numbered with it until it is complete (or Enter picks the first).
+fn label_filter(labels: &[String]) -> impl Fn(&str, &String, &str, usize) -> bool + '_ {
+    move |input, _, text, idx| {
+        let input = input.trim().to_lowercase();
+        let searched = text.to_lowercase().contains(&input);
+        let incomplete = !input.is_empty()
+            && labels
+                .iter()
+                .any(|label| label.len() > input.len() && label.starts_with(&input));
+
+        match (labels.contains(&input), incomplete) {
+            (_, true) => labels[idx].starts_with(&input) || searched,
+            (true, false) => labels[idx] == input,
+            (false, false) => searched,
+        }
+    }
+}
+
+/// Print `message` and read a line from stdin, which isn't a terminal. The
+/// session ends when stdin does, as with `:quit`.
+fn read_plain_line(message: &str) -> String {
+    print!("{} ", message.bold());
+    let _ = io::stdout().flush();
+
+    let mut line = String::new();
+    let read = io::stdin().read_line(&mut line);
+    // The answer isn't echoed, so end the prompt's line
+    println!();
+
+    match read {
+        Ok(0) | Err(_) => {
+            commands::quit();
+            String::new()
+        }
+        Ok(_) => line.trim_end_matches(['\r', '\n']).to_string(),
+    }
+}
+
+fn print_labelled_options(options: &[String], labels: &[String]) {
+    for option in labelled(options, labels) {
+        println!("   {}", option);
+    }
+}
+
+fn render_timed_prompt(message: &str, remaining: Duration, text: &str) -> io::Result<()> {
+    let secs = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+    let countdown = match secs {
+        0..=5 => format!("[{}s]", secs).red().bold(),
+        _ => format!("[{}s]", secs).yellow(),
+    };
+
+    let mut stdout = io::stdout();
+    queue!(
+        stdout,
+        MoveToColumn(0),
+        Clear(ClearType::CurrentLine),
+        Print(format!(
+            "{} {} {} {}",
+            "?".green(),
+            message,
+            countdown,
+            text
+        ))
+    )?;
+    stdout.flush()
+}
+
+fn read_line_until(message: &str, deadline: Instant) -> io::Result<Option<String>> {
+    let mut text = String::new();
+
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(None);
+        }
+        render_timed_prompt(message, deadline - now, &text)?;
+
+        // Wake up regularly to redraw the countdown
+        if !event::poll((deadline - now).min(Duration::from_millis(200)))? {
+            continue;
+        }
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Release {
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    commands::quit();
+                    return Ok(None);
+                }
+                KeyCode::Enter => return Ok(Some(text)),
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                KeyCode::Char(c) => text.push(c),
+                _ => (),
+            }
+        }
+    }
+}
+
+impl Ui for LineUi {
+    fn show_title(&mut self, title: &str, subtitle: &str) {
+        let font = FIGfont::standard().unwrap();
+        println!("{}", font.convert(title).unwrap().to_string().cyan());
+        println!(" {}\n", subtitle.blue());
+    }
+
+    fn show_question(&mut self, question: &str) {
+        println!(" {}", question.bold());
+    }
+
+    fn print(&mut self, spans: &[Span]) {
+        let line = spans
+            .iter()
+            .map(|span| colorize(span).to_string())
+            .collect::<String>();
+        println!("{}", line);
+    }
+
+    fn read_line(&mut self, message: &str, deadline: Option<Instant>) -> Option<String> {
+        // Without a terminal there are no prompts or countdowns, only lines
+        if !self.has_menus() {
+            let line = read_plain_line(message);
+            return (!commands::has_quit()).then_some(line);
+        }
+
+        let deadline = match deadline {
+            Some(deadline) => deadline,
+            None => {
+                return match Text::new(message).prompt() {
+                    Ok(text) => Some(text),
+                    Err(err) => {
+                        quit_on_interrupt(err);
+                        None
+                    }
+                }
+            }
+        };
+
+        let result = terminal::enable_raw_mode().and_then(|_| read_line_until(message, deadline));
+        let _ = terminal::disable_raw_mode();
+
+        match result {
+            Ok(Some(text)) => {
+                println!();
+                Some(text)
+            }
+            Ok(None) if commands::has_quit() => {
+                println!();
+                None
+            }
+            Ok(None) => {
+                println!("\n{}", "⏰ Time's up!".red().bold());
+                None
+            }
+            Err(_) => panic!("Failed to read from stdin!"),
+        }
+    }
+
+    fn select(&mut self, message: &str, options: &[String]) -> usize {
+        if !self.has_menus() {
+            return self.select_labelled(message, options, &option_labels(options.len()));
+        }
+
+        match Select::new(message, options.to_vec()).raw_prompt() {
+            Ok(option) => option.index,
+            Err(err) => {
+                quit_on_interrupt(err);
+                0
+            }
+        }
+    }
+
+    fn select_labelled(&mut self, message: &str, options: &[String], labels: &[String]) -> usize {
+        if !self.has_menus() {
+            print_labelled_options(options, labels);
+            loop {
+                match parse_option(&read_plain_line(message), options) {
+                    Some(idx) => return idx,
+                    None if commands::has_quit() => return 0,
+                    None => println!("Enter the letter of one of the options"),
+                }
+            }
+        }
+
+        let filter = label_filter(labels);
+        let prompt = Select::new(message, labelled(options, labels)).with_filter(&filter);
+
+        match prompt.raw_prompt() {
+            Ok(option) => option.index,
+            Err(err) => {
+                quit_on_interrupt(err);
+                0
+            }
+        }
+    }
+
+    fn multi_select(&mut self, message: &str, options: &[String], labels: &[String]) -> Vec<usize> {
+        if !self.has_menus() {
+            print_labelled_options(options, labels);
+            loop {
+                let input = read_plain_line(message);
+                match parse_options(&input, options.len()) {
+                    Some(picked) => return picked,
+                    None if input.trim().is_empty() => return Vec::new(),
+                    None => println!("Enter the letters of the options to pick"),
+                }
+            }
+        }
+
+        let filter = label_filter(labels);
+        let prompt = MultiSelect::new(message, labelled(options, labels)).with_filter(&filter);
+
+        match prompt.raw_prompt() {
+            Ok(picked) => picked.iter().map(|option| option.index).collect(),
+            Err(err) => {
+                quit_on_interrupt(err);
+                Vec::new()
+            }
+        }
+    }
+
+    fn confirm(&mut self, message: &str) -> bool {
+        if !self.has_menus() {
+            loop {
+                match read_plain_line(&format!("{} (y/n)", message))
+                    .trim()
+                    .to_lowercase()
+                    .as_str()
+                {
+                    "y" | "yes" => return true,
+                    "n" | "no" => return false,
+                    _ if commands::has_quit() => return false,
+                    _ => println!("Answer with y or n"),
+                }
+            }
+        }
+
+        match Confirm::new(message).prompt() {
+            Ok(answer) => answer,
+            Err(err) => {
+                quit_on_interrupt(err);
+                false
+            }
+        }
+    }
+
+    // Menus need a terminal to read keys from
+    fn has_menus(&self) -> bool {
+        io::stdin().is_terminal()
+    }
+}
+
+thread_local! {
+    static UI: RefCell<Box<dyn Ui>> = RefCell::new(Box::new(LineUi));
+    static UI_KIND: Cell<UiKind> = const { Cell::new(UiKind::Line) };
+}
+
+fn with_ui<R, F: FnOnce(&mut dyn Ui) -> R>(f: F) -> R {
+    UI.with(|ui| f(ui.borrow_mut().as_mut()))
+}
+
+/// Switch to another frontend.
+pub fn set_ui(kind: UiKind) -> Result<(), AskmeError> {
+    if UI_KIND.with(|current| current.get()) == kind {
+        return Ok(());
+    }
+
+    let ui: Box<dyn Ui> = match kind {
+        UiKind::Line => Box::new(LineUi),
+        UiKind::Tui => match TuiUi::new() {
+            Ok(ui) => Box::new(ui),
+            Err(e) => {
+                return Err(AskmeError::new(
+                    format!("Failed to start the terminal UI: {}", e),
+                    Some(Box::from(e)),
+                ))
+            }
+        },
+        UiKind::Machine => Box::new(MachineUi::new()),
+    };
+
+    UI.with(|current| *current.borrow_mut() = ui);
+    UI_KIND.with(|current| current.set(kind));
+    Ok(())
+}
+
+/// Let the frontend wrap up the session. The terminal UI then makes way for
+/// printing lines, so what is printed afterwards stays on the screen.
+pub fn finish() {
+    with_ui(|ui| ui.finish());
+
+    if UI_KIND.with(|kind| kind.get()) == UiKind::Tui {
+        UI.with(|current| *current.borrow_mut() = Box::new(LineUi));
+        UI_KIND.with(|kind| kind.set(UiKind::Line));
+    }
+}
+
+pub fn show_title(title: &str, subtitle: &str) {
+    with_ui(|ui| ui.show_title(title, subtitle))
+}
+
+pub fn show_question(question: &str) {
+    with_ui(|ui| ui.show_question(question))
+}
+
+pub fn print_spans(spans: &[Span]) {
+    with_ui(|ui| ui.print(spans))
+}
+
+pub fn print(tone: Tone, text: &str) {
+    print_spans(&[Span::new(tone, text)])
+}
+
+/// Print a line of text followed by an empty line.
+pub fn print_spaced(tone: Tone, text: &str) {
+    print(tone, text);
+    print(Tone::Plain, "");
+}
+
+/// Print a bold label followed by plain text.
+pub fn print_labelled(label: &str, text: &str) {
+    print_spans(&[Span::new(Tone::Bold, label), Span::plain(text)])
+}
+
+/// Print `options` with their labels, a), b), c)... or 1), 2), 3)... when
+/// there are more options than letters.
+pub fn print_options(options: &[String]) {
+    for option in labelled(options, &option_labels(options.len())) {
+        print(Tone::Plain, &format!("   {}", option));
+    }
+}
+
+/// Ask the frontend for something with `prompt`, unless the user has quit
+/// (before or during the prompt), which returns `None`.
+fn prompt<R, F: FnOnce(&mut dyn Ui) -> R>(prompt: F) -> Option<R> {
+    if commands::has_quit() {
+        return None;
+    }
+
+    let answer = with_ui(prompt);
+    (!commands::has_quit()).then_some(answer)
+}
+
+/// Read a line of text, giving up at `deadline` if there is one. Returns
+/// `None` if the time ran out or the user quit.
+pub fn read_line(message: &str, deadline: Option<Instant>) -> Option<String> {
+    prompt(|ui| ui.read_line(message, deadline)).flatten()
+}
+
+/// Let the user pick one of `options`. Returns `None` if the user quit.
+pub fn select(message: &str, options: &[String]) -> Option<usize> {
+    prompt(|ui| ui.select(message, options))
+}
+
+/// Let the user pick one of `options`, shown with `labels`. Returns `None`
+/// if the user quit.
+pub fn select_labelled(message: &str, options: &[String], labels: &[String]) -> Option<usize> {
+    prompt(|ui| ui.select_labelled(message, options, labels))
+}
+
+/// Let the user pick any number of `options`, shown with `labels`. Returns
+/// `None` if the user quit.
+pub fn multi_select(message: &str, options: &[String], labels: &[String]) -> Option<Vec<usize>> {
+    prompt(|ui| ui.multi_select(message, options, labels))
+}
+
+pub fn has_menus() -> bool {
+    with_ui(|ui| ui.has_menus())
+}
+
+/// Ask the user a yes/no question. Returns `None` if the user quit.
+pub fn confirm(message: &str) -> Option<bool> {
+    prompt(|ui| ui.confirm(message))
+}
+
+pub fn pause(secs: f64) {
+    with_ui(|ui| ui.pause(secs))
+}
+
+pub fn show_progress(done: usize, total: usize) {
+    with_ui(|ui| ui.show_progress(done, total))
+}
+
+pub fn show_result(result: &QuestionResult) {
+    with_ui(|ui| ui.show_result(result))
+}
+
+pub fn undo_results(count: usize) {
+    with_ui(|ui| ui.undo_results(count))
+}
+
+pub fn show_score(summary: &Summary, grade: &str) {
+    with_ui(|ui| ui.show_score(summary, grade))
+}
+
+pub fn notify(notice: Notice, message: &str) {
+    with_ui(|ui| ui.notify(notice, message))
+}
+
+/// A frontend answering the menus and confirmations from a script, for
+/// testing what asks them. Nothing is shown.
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct ScriptedUi {
+    pub selects: std::collections::VecDeque<usize>, // Options picked, in order
+    pub confirms: std::collections::VecDeque<bool>, // Answers to confirmations, in order
+}
+
+#[cfg(test)]
+impl Ui for ScriptedUi {
+    fn show_title(&mut self, _title: &str, _subtitle: &str) {}
+
+    fn show_question(&mut self, _question: &str) {}
+
+    fn print(&mut self, _spans: &[Span]) {}
+
+    fn read_line(&mut self, _message: &str, _deadline: Option<Instant>) -> Option<String> {
+        None
+    }
+
+    fn select(&mut self, message: &str, _options: &[String]) -> usize {
+        self.selects
+            .pop_front()
+            .unwrap_or_else(|| panic!("no pick scripted for {:?}", message))
+    }
+
+    fn multi_select(
+        &mut self,
+        message: &str,
+        _options: &[String],
+        _labels: &[String],
+    ) -> Vec<usize> {
+        panic!("no picks scripted for {:?}", message)
+    }
+
+    fn confirm(&mut self, message: &str) -> bool {
+        self.confirms
+            .pop_front()
+            .unwrap_or_else(|| panic!("no answer scripted for {:?}", message))
+    }
+}
+
+/// Switch this thread to `ui`.
+#[cfg(test)]
+pub(crate) fn set_scripted_ui(ui: ScriptedUi) {
+    UI.with(|current| *current.borrow_mut() = Box::new(ui));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The options of a menu of `len` options left by typing `input`. Option
+    /// n is n x's long, so that numbers only match labels.
+    fn filtered(len: usize, input: &str) -> Vec<usize> {
+        let options = (1..=len).map(|n| "x".repeat(n)).collect::<Vec<String>>();
+        let labels = option_labels(len);
+        let filter = label_filter(&labels);
+
+        (0..len)
+            .filter(|&idx| filter(input, &options[idx], &options[idx], idx))
+            .collect()
+    }
+
+    #[test]
+    fn letters_leave_their_option_right_away() {
+        assert_eq!(filtered(4, "b"), vec![1]);
+        assert_eq!(filtered(4, " C "), vec![2]);
+        assert_eq!(filtered(4, ""), vec![0, 1, 2, 3]);
+        assert_eq!(filtered(4, "xxx"), vec![2, 3]);
+    }
+
+    #[test]
+    fn numbers_wait_until_they_are_complete() {
+        // "1" could still become 10 to 19
+        assert_eq!(
+            filtered(30, "1"),
+            [0, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18]
+        );
+        assert_eq!(filtered(30, "12"), vec![11]);
+        assert_eq!(filtered(30, "4"), vec![3]);
+        assert_eq!(filtered(30, "3"), vec![2, 29]);
+        assert_eq!(filtered(30, "30"), vec![29]);
+    }
+}
@@ -0,0 +1,161 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Property tests for the multiple choice generators, on small sets full of
+/// duplicate answers.
+use std::collections::HashSet;
+
+use askme::generate::{choices_available, multiple_choices, multiple_select_choices, Distractors};
+use askme::prelude::*;
+use askme::random::seed_rng;
+use proptest::prelude::*;
+
+/// Answers and tags of a question.
+type Parts = (Vec<String>, Vec<String>);
+
+fn question(idx: usize, answers: Vec<String>, tags: Vec<String>) -> Question {
+    Question {
+        title: format!("Q{}", idx),
+        answers,
+        kind: QuestionKind::Single,
+        ordered: false,
+        tags,
+        weight: 1.0,
+        points: 1.0,
+        select_all: None,
+        source: String::new(),
+    }
+}
+
+/// Answers drawn from a handful of values, so that sets repeat them a lot,
+/// sometimes in another case or with spaces around them.
+fn answers() -> impl Strategy<Value = Vec<String>> {
+    let values = ["a", "A", "b", " b", "c", "1", "10", "H2O", "h2o "]
+        .map(String::from)
+        .to_vec();
+    prop::collection::vec(prop::sample::select(values), 1..4)
+}
+
+fn tags() -> impl Strategy<Value = Vec<String>> {
+    let values = ["x", "y"].map(String::from).to_vec();
+    prop::collection::vec(prop::sample::select(values), 0..2)
+}
+
+/// A set of up to 5 questions, possibly empty, and the question to ask. It
+/// is one of the set's questions unless `outside` is true.
+fn set() -> impl Strategy<Value = (Vec<Parts>, Parts, bool)> {
+    (
+        prop::collection::vec((answers(), tags()), 0..5),
+        (answers(), tags()),
+        any::<bool>(),
+    )
+}
+
+fn strategy() -> impl Strategy<Value = Distractors> {
+    prop::sample::select(vec![
+        Distractors::Random,
+        Distractors::Tags,
+        Distractors::Shape,
+        Distractors::Similar,
+        Distractors::Type,
+    ])
+}
+
+fn build(set: Vec<Parts>, asked: Parts, outside: bool) -> (Vec<Question>, Question) {
+    let mut questions = set
+        .into_iter()
+        .enumerate()
+        .map(|(idx, (answers, tags))| question(idx, answers, tags))
+        .collect::<Vec<Question>>();
+
+    let asked = question(questions.len(), asked.0, asked.1);
+    if !outside {
+        questions.push(asked.clone());
+    }
+
+    (questions, asked)
+}
+
+/// Answers differing only in case or surrounding spaces are the same option.
+fn key(answer: &str) -> String {
+    answer.trim().to_lowercase()
+}
+
+fn is_distinct(choices: &[String]) -> bool {
+    choices
+        .iter()
+        .map(|c| key(c))
+        .collect::<HashSet<String>>()
+        .len()
+        == choices.len()
+}
+
+fn is_answer(question: &Question, choice: &str) -> bool {
+    question
+        .answers
+        .iter()
+        .any(|answer| key(answer) == key(choice))
+}
+
+proptest! {
+    #[test]
+    fn multiple_choices_are_distinct_with_one_answer(
+        (set, asked, outside) in set(),
+        len in 0usize..8,
+        distractors in strategy(),
+        seed in any::<u64>(),
+    ) {
+        seed_rng(seed);
+        let (questions, asked) = build(set, asked, outside);
+
+        let (choices, correct) = multiple_choices(&asked, &questions, len, distractors);
+
+        prop_assert_eq!(choices.len(), len.max(1).min(choices_available(&asked, &questions)));
+        prop_assert!(correct < choices.len());
+        prop_assert!(is_distinct(&choices));
+        for (idx, choice) in choices.iter().enumerate() {
+            prop_assert_eq!(is_answer(&asked, choice), idx == correct);
+        }
+    }
+
+    #[test]
+    fn multiple_select_choices_are_distinct_with_some_answers(
+        (set, asked, outside) in set(),
+        len in 0usize..8,
+        distractors in strategy(),
+        seed in any::<u64>(),
+    ) {
+        seed_rng(seed);
+        let (questions, asked) = build(set, asked, outside);
+
+        let (choices, correct) = multiple_select_choices(&asked, &questions, len, distractors);
+
+        prop_assert!(!choices.is_empty());
+        prop_assert!(choices.len() <= len.max(1));
+        prop_assert!(!correct.is_empty());
+        prop_assert!(is_distinct(&choices));
+        for (idx, choice) in choices.iter().enumerate() {
+            prop_assert_eq!(is_answer(&asked, choice), correct.contains(&idx));
+        }
+
+        // A wrong choice is shown whenever there is one to show and room for it
+        let has_wrong = questions
+            .iter()
+            .flat_map(|qn| &qn.answers)
+            .any(|answer| !is_answer(&asked, answer));
+        if has_wrong && len > 1 {
+            prop_assert!(correct.len() < choices.len());
+        }
+    }
+}
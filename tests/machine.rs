@@ -0,0 +1,228 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Modes driven with `--machine`, with JSON lines piped into them.
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use serde_json::Value;
+
+const SET: &str = "
+title: Elements
+subtitle: Symbols
+questions:
+  - title: H
+    answers: [Hydrogen]
+  - title: He
+    answers: [Helium]
+  - title: Li
+    answers: [Lithium]
+";
+
+/// Write `set` into a file of its own for the test called `name`.
+fn set_file(name: &str, set: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("askme-{}-{}.yaml", name, std::process::id()));
+    std::fs::write(&path, set).unwrap();
+    path
+}
+
+/// Run `binary` on `set` with `--machine` and `args`, piping in `input`.
+/// Returns the messages it printed.
+fn run(binary: &str, name: &str, set: &str, args: &[&str], input: &str) -> Vec<Value> {
+    let set = set_file(name, set);
+    let mut child = Command::new(binary)
+        .arg("--machine")
+        .args(args)
+        .arg(&set)
+        .env("XDG_DATA_HOME", std::env::temp_dir().join("askme-tests"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    std::fs::remove_file(set).unwrap();
+    assert!(output.status.success());
+
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+fn of_type<'a>(messages: &'a [Value], kind: &str) -> Vec<&'a Value> {
+    messages.iter().filter(|m| m["type"] == kind).collect()
+}
+
+#[test]
+fn quitting_still_prints_the_score() {
+    let messages = run(
+        env!("CARGO_BIN_EXE_askme-memorize"),
+        "machine-quit",
+        SET,
+        &[],
+        "{\"type\": \"answer\", \"answer\": \"Hydrogen\"}\n{\"type\": \"quit\"}\n",
+    );
+
+    assert_eq!(of_type(&messages, "prompt").len(), 2);
+    assert_eq!(of_type(&messages, "result").len(), 1);
+    let score = of_type(&messages, "score");
+    assert_eq!(score.len(), 1);
+    assert_eq!(score[0]["score"], 1.0);
+    assert_eq!(score[0]["total"], 1);
+    assert_eq!(messages.last().unwrap()["type"], "score");
+}
+
+#[test]
+fn closing_stdin_ends_the_session() {
+    let messages = run(
+        env!("CARGO_BIN_EXE_askme-memorize"),
+        "machine-eof",
+        SET,
+        &[],
+        "{\"type\": \"answer\", \"answer\": \"Hydrogen\"}\n{\"type\": \"answer\", \"answer\": \"Neon\"}\n",
+    );
+
+    let score = of_type(&messages, "score");
+    assert_eq!(score.len(), 1);
+    assert_eq!(score[0]["score"], 1.0);
+    assert_eq!(score[0]["total"], 2);
+}
+
+#[test]
+fn invalid_lines_are_answered_with_an_error() {
+    let messages = run(
+        env!("CARGO_BIN_EXE_askme-memorize"),
+        "machine-invalid",
+        SET,
+        &[],
+        "not json\n{\"type\": \"answer\", \"answer\": [1]}\n{\"type\": \"answer\", \"answer\": \"Hydrogen\"}\n",
+    );
+
+    assert_eq!(of_type(&messages, "error").len(), 2);
+    assert_eq!(of_type(&messages, "score")[0]["score"], 1.0);
+}
+
+#[test]
+fn multiple_choice_takes_labels() {
+    let messages = run(
+        env!("CARGO_BIN_EXE_askme-multichoice"),
+        "machine-multichoice",
+        SET,
+        &["--max-choices", "3"],
+        "{\"type\": \"answer\", \"answer\": \"Hydrogen\"}\n",
+    );
+
+    // The second question is shown before stdin is found closed
+    let select = of_type(&messages, "select");
+    assert_eq!(select.len(), 2);
+    assert_eq!(select[0]["labels"], serde_json::json!(["a", "b", "c"]));
+    let score = of_type(&messages, "score");
+    assert_eq!(score[0]["score"], 1.0);
+    assert_eq!(score[0]["total"], 1);
+}
+
+/// Answer lines typing each of `answers`.
+fn answers(answers: &[&str]) -> String {
+    answers
+        .iter()
+        .map(|answer| format!("{{\"type\": \"answer\", \"answer\": \"{}\"}}\n", answer))
+        .collect()
+}
+
+#[test]
+fn undo_takes_the_last_answer_back() {
+    let messages = run(
+        env!("CARGO_BIN_EXE_askme-memorize"),
+        "machine-undo",
+        SET,
+        &[],
+        &answers(&["Hydrogen", "Neon", ":undo", "Lithium"]),
+    );
+
+    let undo = of_type(&messages, "undo");
+    assert_eq!(undo.len(), 1);
+    assert_eq!(undo[0]["count"], 1);
+    let score = of_type(&messages, "score");
+    assert_eq!(score[0]["score"], 2.0);
+    assert_eq!(score[0]["total"], 2);
+}
+
+#[test]
+fn undo_asks_the_question_again_until_mastered() {
+    let messages = run(
+        env!("CARGO_BIN_EXE_askme-memorize"),
+        "machine-undo-mastered",
+        SET,
+        &["--until-mastered=1"],
+        &answers(&["Hydrogen", "Neon", ":undo", "Lithium", "Helium"]),
+    );
+
+    let questions = of_type(&messages, "question")
+        .iter()
+        .map(|m| m["text"].as_str().unwrap())
+        .collect::<Vec<&str>>();
+    assert_eq!(questions, ["H", "He", "Li", "Li", "He"]);
+
+    let texts = of_type(&messages, "text")
+        .iter()
+        .map(|m| m["text"].as_str().unwrap())
+        .collect::<Vec<&str>>();
+    for attempts in ["H: 1", "He: 1", "Li: 1"] {
+        assert!(
+            texts.contains(&attempts),
+            "no {:?} in {:?}",
+            attempts,
+            texts
+        );
+    }
+    assert_eq!(of_type(&messages, "score")[0]["score"], 3.0);
+}
+
+#[test]
+fn ordering_questions_give_no_hints() {
+    let set = "
+title: Elements
+subtitle: Symbols
+questions:
+  - title: By number
+    answers: [Hydrogen, Helium, Lithium]
+    kind: order
+";
+    let messages = run(
+        env!("CARGO_BIN_EXE_askme-memorize"),
+        "machine-hint",
+        set,
+        &[],
+        &answers(&[":hint"]),
+    );
+
+    let warnings = of_type(&messages, "warning");
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(
+        warnings[0]["message"],
+        "there are no hints for ordering questions"
+    );
+    assert!(of_type(&messages, "text")
+        .iter()
+        .all(|m| !m["text"].as_str().unwrap().starts_with("Hint")));
+}
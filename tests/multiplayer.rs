@@ -0,0 +1,172 @@
+// Copyright 2023 Eason Qin <eason@ezntek.com> and Cikitta Tjok <daringcuteseal@gmail.com>.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// Games of `askme host` and `askme join` played on localhost, and what
+/// either does with settings or messages it can't use.
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::{Child, Command, Output, Stdio};
+
+const SET: &str = "
+title: Elements
+subtitle: Symbols
+questions:
+  - title: H
+    answers: [Hydrogen]
+  - title: He
+    answers: [Helium]
+";
+
+/// Write the set into a file of its own for the test called `name`.
+fn set_file(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("askme-{}-{}.yaml", name, std::process::id()));
+    std::fs::write(&path, SET).unwrap();
+    path
+}
+
+fn askme(args: &[&str]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_askme"));
+    command
+        .args(args)
+        .env("XDG_DATA_HOME", std::env::temp_dir().join("askme-tests"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    command
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+/// Read `child`'s output until a line containing `text`.
+fn wait_for(child: &mut Child, text: &str) {
+    let stdout = child.stdout.as_mut().unwrap();
+    let mut line = String::new();
+    let mut reader = BufReader::new(stdout);
+    loop {
+        line.clear();
+        assert!(
+            reader.read_line(&mut line).unwrap() > 0,
+            "no \"{}\" in the output",
+            text
+        );
+        if line.contains(text) {
+            return;
+        }
+    }
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).to_string()
+}
+
+#[test]
+fn a_game_is_played_to_the_final_leaderboard() {
+    let set = set_file("game");
+    let port = free_port().to_string();
+
+    let mut host = askme(&["host", "--bind", "127.0.0.1", "-p", &port, "-d", "0"])
+        .arg(&set)
+        .spawn()
+        .unwrap();
+    wait_for(&mut host, "hosting on port");
+
+    let mut join = askme(&["join", &format!("127.0.0.1:{}", port), "-n", "Ann"])
+        .spawn()
+        .unwrap();
+    join.stdin
+        .as_mut()
+        .unwrap()
+        .write_all(b"Hydrogen\nLithium\n")
+        .unwrap();
+    wait_for(&mut join, "waiting for the host to start");
+
+    // Start the game
+    host.stdin.as_mut().unwrap().write_all(b"\n").unwrap();
+
+    let joined = join.wait_with_output().unwrap();
+    let hosted = host.wait_with_output().unwrap();
+    assert!(joined.status.success(), "{}", stderr(&joined));
+    assert!(hosted.status.success(), "{}", stderr(&hosted));
+
+    let joined = stdout(&joined);
+    assert!(joined.contains("Final leaderboard"));
+    assert!(joined.contains("Correct answers: 1"));
+    assert!(joined.contains("Answer: Helium"));
+
+    std::fs::remove_file(set).unwrap();
+}
+
+#[test]
+fn host_rejects_a_question_time_it_cant_wait_for() {
+    let set = set_file("question-time");
+
+    for time in ["-1", "0", "NaN", "inf", "1e20"] {
+        let output = askme(&["host", "--bind", "127.0.0.1", "-p", "0"])
+            .arg(format!("--question-time={}", time))
+            .arg(&set)
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        assert!(
+            stdout(&output).contains("question_time"),
+            "{}",
+            stderr(&output)
+        );
+        assert!(!stderr(&output).contains("panicked"), "{}", stderr(&output));
+    }
+
+    std::fs::remove_file(set).unwrap();
+}
+
+#[test]
+fn join_rejects_questions_it_cant_ask() {
+    let questions = [
+        r#"{"type":"question","number":0,"total":1,"title":"H","kind":"single","time_limit":5}"#,
+        r#"{"type":"question","number":1,"total":1,"title":"H","kind":"single","time_limit":-1}"#,
+        r#"{"type":"question","number":1,"total":1,"title":"H","kind":"single","time_limit":0}"#,
+        r#"{"type":"question","number":1,"total":1,"title":"H","kind":"single","time_limit":1e300}"#,
+    ];
+
+    for question in questions {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let join = askme(&["join", &address, "-n", "Ann"]).spawn().unwrap();
+
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line).unwrap();
+        assert!(line.contains("\"join\""));
+        writeln!(stream, "{}", question).unwrap();
+
+        let output = join.wait_with_output().unwrap();
+        assert!(!output.status.success());
+        assert!(
+            stdout(&output).contains("Invalid message from the host"),
+            "{}",
+            stderr(&output)
+        );
+        assert!(!stderr(&output).contains("panicked"), "{}", stderr(&output));
+    }
+}